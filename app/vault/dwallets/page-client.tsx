@@ -29,10 +29,12 @@ import {
   buildFreezeGuardedDwalletIx,
   buildUnfreezeGuardedDwalletIx,
   buildApproveGuardedMessageIx,
+  DailyWindow,
 } from "@/lib/dwallet-guard/instructions";
 import {
   GuardedDwallet,
   GuardSigningRequest,
+  REJECTION_REASONS,
 } from "@/lib/hooks/use-dwallet-guard";
 import {
   Shield,
@@ -156,12 +158,8 @@ export default function DwalletGuardPageClient() {
     try {
       const [cpiAuthority] = deriveCpiAuthority();
       let guardedDwallet = publicKey.toBase58();
-      if (selectedAgent && dwalletInput) {
-        const [gd] = deriveGuardedDwallet(
-          publicKey,
-          new PublicKey(selectedAgent),
-          new PublicKey(dwalletInput)
-        );
+      if (dwalletInput) {
+        const [gd] = deriveGuardedDwallet(new PublicKey(dwalletInput));
         guardedDwallet = gd.toBase58();
       }
       const [gsr] = deriveGuardSigningRequest(
@@ -183,7 +181,6 @@ export default function DwalletGuardPageClient() {
     deriveCpiAuthority,
     deriveGuardedDwallet,
     deriveGuardSigningRequest,
-    selectedAgent,
     dwalletInput,
     requestIdHex,
   ]);
@@ -398,6 +395,7 @@ export default function DwalletGuardPageClient() {
         agent: PHASE4B.agent,
         humanrailCapability: PHASE4B.capability,
         dwallet: PHASE4B.dwallet,
+        cpiAuthority: PHASE4B.cpiAuthority,
         allowedChainId: 84532,
         allowedAssetHash: hashPolicyInput("USDC:BASE_SEPOLIA"),
         allowedRecipientHash: hashPolicyInput("0x1111111111111111111111111111111111111111"),
@@ -405,6 +403,9 @@ export default function DwalletGuardPageClient() {
         dailyLimit: BigInt(500_000_000),
         totalLimit: BigInt(1_000_000_000),
         expiresAt,
+        dailyWindow: DailyWindow.CalendarDay,
+        notBefore: BigInt(0),
+        minIntervalSecs: BigInt(0),
       })
     );
   }, [guardProgramId, PHASE4B, sendDemoTx]);
@@ -413,7 +414,7 @@ export default function DwalletGuardPageClient() {
     if (!guardProgramId || !PHASE4B) return;
     sendDemoTx("Freeze", () =>
       buildFreezeGuardedDwalletIx(guardProgramId, {
-        principal: PHASE4B.principal,
+        authority: PHASE4B.principal,
         guardedDwallet: PHASE4B.guardedDwallet,
       })
     );
@@ -447,6 +448,8 @@ export default function DwalletGuardPageClient() {
         guardedDwallet: PHASE4B.guardedDwallet,
         guardSigningRequest: requestPda,
         dwallet: PHASE4B.dwallet,
+        humanProfile: PHASE4B.humanProfile,
+        humanrailCapability: PHASE4B.capability,
         cpiAuthority: PHASE4B.cpiAuthority,
         coordinator: dummyCoordinator,
         messageApproval: dummyMessageApproval,
@@ -460,6 +463,7 @@ export default function DwalletGuardPageClient() {
         userPubkey: PHASE4B.principal.toBytes(),
         signatureScheme: 0,
         messageApprovalBump: 0,
+        payload: { kind: "digest" },
       }),
       "Rejected request recorded; Ika CPI was not called."
     );
@@ -727,7 +731,7 @@ export default function DwalletGuardPageClient() {
                 </div>
                 <div className="space-y-1">
                   <span className="text-xs text-neutral-500">
-                    GuardedDwallet {"["}guarded_dwallet, principal, agent, dwallet{"]"}
+                    GuardedDwallet {"["}guarded_dwallet, dwallet{"]"}
                   </span>
                   <div className="flex items-center gap-2">
                     <code className="block flex-1 truncate rounded bg-black/30 px-2 py-1 text-xs text-neutral-300">
@@ -849,20 +853,24 @@ export default function DwalletGuardPageClient() {
                   <div className="grid grid-cols-2 gap-2 text-xs">
                     <div className="text-neutral-500">Principal</div>
                     <code className="truncate text-neutral-400">{fetchedGuarded.principal.toBase58()}</code>
-                    <div className="text-neutral-500">Agent</div>
-                    <code className="truncate text-neutral-400">{fetchedGuarded.agent.toBase58()}</code>
+                    <div className="text-neutral-500">Agents</div>
+                    <code className="truncate text-neutral-400">
+                      {fetchedGuarded.agents.map((entry) => entry.agent.toBase58()).join(", ") || "—"}
+                    </code>
                     <div className="text-neutral-500">dWallet</div>
                     <code className="truncate text-neutral-400">{fetchedGuarded.dwallet.toBase58()}</code>
                     <div className="text-neutral-500">Allowed Chain</div>
-                    <code className="truncate text-neutral-400">{fetchedGuarded.allowedChainId}</code>
+                    <code className="truncate text-neutral-400">{fetchedGuarded.allowedChainIds.join(", ")}</code>
                     <div className="text-neutral-500">Per-Tx Limit</div>
-                    <code className="truncate text-neutral-400">{fetchedGuarded.perTxLimit.toString()}</code>
+                    <code className="truncate text-neutral-400">{fetchedGuarded.assets[0]?.limits.perTxLimit.toString() ?? "—"}</code>
                     <div className="text-neutral-500">Daily Limit</div>
-                    <code className="truncate text-neutral-400">{fetchedGuarded.dailyLimit.toString()}</code>
+                    <code className="truncate text-neutral-400">{fetchedGuarded.assets[0]?.limits.dailyLimit.toString() ?? "—"}</code>
                     <div className="text-neutral-500">Total Limit</div>
-                    <code className="truncate text-neutral-400">{fetchedGuarded.totalLimit.toString()}</code>
+                    <code className="truncate text-neutral-400">{fetchedGuarded.assets[0]?.limits.totalLimit.toString() ?? "—"}</code>
                     <div className="text-neutral-500">Frozen</div>
                     <code className="truncate text-neutral-400">{fetchedGuarded.frozen ? "Yes" : "No"}</code>
+                    <div className="text-neutral-500">Armed</div>
+                    <code className="truncate text-neutral-400">{fetchedGuarded.armed ? "Yes" : "No"}</code>
                     <div className="text-neutral-500">Expires At</div>
                     <code className="truncate text-neutral-400">{fetchedGuarded.expiresAt.toString()}</code>
                   </div>
@@ -875,8 +883,8 @@ export default function DwalletGuardPageClient() {
                   <div className="grid grid-cols-2 gap-2 text-xs">
                     <div className="text-neutral-500">Status</div>
                     <code className="truncate text-neutral-400">{fetchedRequest.status}</code>
-                    <div className="text-neutral-500">Rejection Code</div>
-                    <code className="truncate text-neutral-400">{fetchedRequest.rejectionCode}</code>
+                    <div className="text-neutral-500">Rejection Reason</div>
+                    <code className="truncate text-neutral-400">{REJECTION_REASONS[fetchedRequest.rejectionReason] ?? fetchedRequest.rejectionReason}</code>
                     <div className="text-neutral-500">Ika Message Approval</div>
                     <code className="truncate text-neutral-400">{fetchedRequest.ikaMessageApproval.toBase58()}</code>
                     <div className="text-neutral-500">Amount</div>
@@ -1380,11 +1388,11 @@ export default function DwalletGuardPageClient() {
                     <div className="text-neutral-500">dWallet</div>
                     <code className="truncate text-neutral-400">{realGuardedDwallet.dwallet.toBase58().slice(0, 16)}…</code>
                     <div className="text-neutral-500">Per-tx limit</div>
-                    <code className="truncate text-neutral-400">{realGuardedDwallet.perTxLimit.toString()}</code>
+                    <code className="truncate text-neutral-400">{realGuardedDwallet.assets[0]?.limits.perTxLimit.toString() ?? "—"}</code>
                     <div className="text-neutral-500">Daily limit</div>
-                    <code className="truncate text-neutral-400">{realGuardedDwallet.dailyLimit.toString()}</code>
+                    <code className="truncate text-neutral-400">{realGuardedDwallet.assets[0]?.limits.dailyLimit.toString() ?? "—"}</code>
                     <div className="text-neutral-500">Total limit</div>
-                    <code className="truncate text-neutral-400">{realGuardedDwallet.totalLimit.toString()}</code>
+                    <code className="truncate text-neutral-400">{realGuardedDwallet.assets[0]?.limits.totalLimit.toString() ?? "—"}</code>
                     <div className="text-neutral-500">Frozen</div>
                     <code className="truncate text-neutral-400">{realGuardedDwallet.frozen ? "Yes" : "No"}</code>
                     <div className="text-neutral-500">Bump</div>
@@ -1534,8 +1542,8 @@ export default function DwalletGuardPageClient() {
                         <code className="truncate text-neutral-400">
                           {phase5dGuardSigningRequest.status === 1 ? "approved" : phase5dGuardSigningRequest.status === 2 ? "rejected" : "unknown"} ({phase5dGuardSigningRequest.status})
                         </code>
-                        <div className="text-neutral-500">Rejection code</div>
-                        <code className="truncate text-neutral-400">{phase5dGuardSigningRequest.rejectionCode}</code>
+                        <div className="text-neutral-500">Rejection reason</div>
+                        <code className="truncate text-neutral-400">{REJECTION_REASONS[phase5dGuardSigningRequest.rejectionReason] ?? phase5dGuardSigningRequest.rejectionReason}</code>
                         <div className="text-neutral-500">IkaMessageApproval</div>
                         <code className="truncate text-neutral-400">{phase5dGuardSigningRequest.ikaMessageApproval.toBase58().slice(0, 16)}…</code>
                       </div>
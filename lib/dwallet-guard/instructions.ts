@@ -15,51 +15,108 @@ const DISCRIMINATORS = {
   freezeGuardedDwallet: Buffer.from([151, 57, 89, 252, 123, 234, 123, 61]),
   unfreezeGuardedDwallet: Buffer.from([223, 101, 174, 85, 26, 221, 221, 194]),
   approveGuardedMessage: Buffer.from([161, 49, 124, 159, 1, 54, 243, 30]),
+  armGuardedDwallet: Buffer.from([251, 146, 121, 177, 238, 200, 225, 89]),
+  setPrincipalDigests: Buffer.from([100, 124, 241, 243, 228, 249, 205, 170]),
 };
 
 // Ika program ID (devnet)
 const IKA_PROGRAM_ID = new PublicKey("87W54kGYFQ1rgWqMeu4XTPHWXWmXSQCcjm8vCTfiq1oY");
 
 // ---------------------------------------------------------------------------
-// Instruction: initialize_guarded_dwallet
-// Accounts (from IDL):
-//   0. principal       [signer, writable]
-//   1. guarded_dwallet [writable]
-//   2. human_profile
-//   3. agent
-//   4. humanrail_capability
-//   5. dwallet
-//   6. system_program
-// Args:
-//   allowed_chain_id: u32
-//   allowed_asset_hash: [u8; 32]
-//   allowed_recipient_hash: [u8; 32]
-//   per_tx_limit: u64
-//   daily_limit: u64
-//   total_limit: u64
-//   expires_at: i64
+// Chain ids for chains whose transactions carry none (SLIP-44 coin type with
+// the BIP-44 hardened bit). Every id below 0x80000000 is an EVM chain id.
 // ---------------------------------------------------------------------------
-export function buildInitializeGuardedDwalletIx(
-  guardProgramId: PublicKey,
-  params: {
-    principal: PublicKey;
-    guardedDwallet: PublicKey;
-    humanProfile: PublicKey;
-    agent: PublicKey;
-    humanrailCapability: PublicKey;
-    dwallet: PublicKey;
-    allowedChainId: number;
-    allowedAssetHash: Uint8Array;
-    allowedRecipientHash: Uint8Array;
-    perTxLimit: bigint;
-    dailyLimit: bigint;
-    totalLimit: bigint;
-    expiresAt: bigint;
+export const BITCOIN_MAINNET_CHAIN_ID = 0x8000_0000;
+export const BITCOIN_TESTNET_CHAIN_ID = 0x8000_0001;
+export const SOLANA_CHAIN_ID = 0x8000_01f5;
+
+// Accounting window for the daily limit (Borsh enum, one byte)
+export enum DailyWindow {
+  CalendarDay = 0,
+  Rolling24h = 1,
+}
+
+// ---------------------------------------------------------------------------
+// SigningPayload: the raw transaction approve_guarded_message decodes.
+// A bare digest is rejected with PayloadRequired unless the principal sends it
+// and has turned on principal digests (set_principal_digests).
+// ---------------------------------------------------------------------------
+export type BitcoinPrevout = {
+  amount: bigint;
+  scriptPubkey: Uint8Array;
+};
+
+export type SigningPayload =
+  | { kind: "digest" }
+  | { kind: "evm"; tx: Uint8Array }
+  | { kind: "bitcoin"; tx: Uint8Array; inputIndex: number; prevouts: BitcoinPrevout[] }
+  | { kind: "solana"; message: Uint8Array };
+
+function u32(value: number): Buffer {
+  const buf = Buffer.alloc(4);
+  buf.writeUInt32LE(value, 0);
+  return buf;
+}
+
+function u64(value: bigint): Buffer {
+  const buf = Buffer.alloc(8);
+  buf.writeBigUInt64LE(value, 0);
+  return buf;
+}
+
+function bytes(value: Uint8Array): Buffer {
+  return Buffer.concat([u32(value.length), Buffer.from(value)]);
+}
+
+export function encodeSigningPayload(payload: SigningPayload): Buffer {
+  switch (payload.kind) {
+    case "digest":
+      return Buffer.from([0]);
+    case "evm":
+      return Buffer.concat([Buffer.from([1]), bytes(payload.tx)]);
+    case "bitcoin":
+      return Buffer.concat([
+        Buffer.from([2]),
+        bytes(payload.tx),
+        u32(payload.inputIndex),
+        u32(payload.prevouts.length),
+        ...payload.prevouts.map((prevout) =>
+          Buffer.concat([u64(prevout.amount), bytes(prevout.scriptPubkey)])
+        ),
+      ]);
+    case "solana":
+      return Buffer.concat([Buffer.from([3]), bytes(payload.message)]);
   }
+}
+
+type InitializeGuardedDwalletParams = {
+  principal: PublicKey;
+  guardedDwallet: PublicKey;
+  humanProfile: PublicKey;
+  agent: PublicKey;
+  humanrailCapability: PublicKey;
+  dwallet: PublicKey;
+  cpiAuthority: PublicKey;
+  allowedChainId: number;
+  allowedAssetHash: Uint8Array;
+  allowedRecipientHash: Uint8Array;
+  perTxLimit: bigint;
+  dailyLimit: bigint;
+  totalLimit: bigint;
+  expiresAt: bigint;
+  dailyWindow: DailyWindow;
+  notBefore: bigint;
+  minIntervalSecs: bigint;
+};
+
+function buildInitializeIx(
+  guardProgramId: PublicKey,
+  discriminator: Buffer,
+  params: InitializeGuardedDwalletParams
 ): TransactionInstruction {
-  const data = Buffer.alloc(8 + 4 + 32 + 32 + 8 + 8 + 8 + 8);
+  const data = Buffer.alloc(8 + 4 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 8 + 8);
   let offset = 0;
-  DISCRIMINATORS.initializeGuardedDwallet.copy(data, offset);
+  discriminator.copy(data, offset);
   offset += 8;
   data.writeUInt32LE(params.allowedChainId, offset);
   offset += 4;
@@ -67,11 +124,14 @@ export function buildInitializeGuardedDwalletIx(
   offset += 32;
   Buffer.from(params.allowedRecipientHash).copy(data, offset);
   offset += 32;
-  const view = new DataView(data.buffer, data.byteOffset + offset, 32);
+  const view = new DataView(data.buffer, data.byteOffset + offset, 49);
   view.setBigUint64(0, params.perTxLimit, true);
   view.setBigUint64(8, params.dailyLimit, true);
   view.setBigUint64(16, params.totalLimit, true);
   view.setBigInt64(24, params.expiresAt, true);
+  view.setUint8(32, params.dailyWindow);
+  view.setBigInt64(33, params.notBefore, true);
+  view.setBigInt64(41, params.minIntervalSecs, true);
 
   return new TransactionInstruction({
     keys: [
@@ -81,6 +141,7 @@ export function buildInitializeGuardedDwalletIx(
       { pubkey: params.agent, isSigner: false, isWritable: false },
       { pubkey: params.humanrailCapability, isSigner: false, isWritable: false },
       { pubkey: params.dwallet, isSigner: false, isWritable: false },
+      { pubkey: params.cpiAuthority, isSigner: false, isWritable: false },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
     ],
     programId: guardProgramId,
@@ -88,76 +149,99 @@ export function buildInitializeGuardedDwalletIx(
   });
 }
 
+// ---------------------------------------------------------------------------
+// Instruction: initialize_guarded_dwallet
+// The dWallet's authority must be the principal or the guard's CPI authority.
+// In the first case the guard starts unarmed until the authority is handed to
+// the CPI authority and armed; in the second it starts armed.
+// Accounts (from IDL):
+//   0. principal       [signer, writable]
+//   1. guarded_dwallet [writable] — PDA ["guarded_dwallet", dwallet]
+//   2. human_profile
+//   3. agent
+//   4. humanrail_capability
+//   5. dwallet
+//   6. cpi_authority   — PDA ["__ika_cpi_authority"]
+//   7. system_program
+// Args:
+//   allowed_chain_id: u32
+//   allowed_asset_hash: [u8; 32]
+//   allowed_recipient_hash: [u8; 32]
+//   per_tx_limit: u64
+//   daily_limit: u64
+//   total_limit: u64
+//   expires_at: i64
+//   daily_window: DailyWindow
+//   not_before: i64
+//   min_interval_secs: i64
+// ---------------------------------------------------------------------------
+export function buildInitializeGuardedDwalletIx(
+  guardProgramId: PublicKey,
+  params: InitializeGuardedDwalletParams
+): TransactionInstruction {
+  return buildInitializeIx(guardProgramId, DISCRIMINATORS.initializeGuardedDwallet, params);
+}
+
 // ---------------------------------------------------------------------------
 // Instruction: initialize_guarded_dwallet_demo
-// Devnet-only — skips HumanRail owner checks on human_profile/agent/capability.
+// Devnet-only, and only in program builds with the `demo` feature — skips
+// HumanRail owner checks on human_profile/agent/capability.
 // Same args and accounts as initialize_guarded_dwallet, but without owner constraints.
 // ---------------------------------------------------------------------------
 export function buildInitializeGuardedDwalletDemoIx(
+  guardProgramId: PublicKey,
+  params: InitializeGuardedDwalletParams
+): TransactionInstruction {
+  return buildInitializeIx(guardProgramId, DISCRIMINATORS.initializeGuardedDwalletDemo, params);
+}
+
+// ---------------------------------------------------------------------------
+// Instruction: arm_guarded_dwallet
+// Signed by the pre-arm authority once the dWallet authority is held by the
+// guard's CPI authority.
+// Accounts:
+//   0. authority       [signer]
+//   1. guarded_dwallet [writable]
+//   2. dwallet
+//   3. cpi_authority
+// ---------------------------------------------------------------------------
+export function buildArmGuardedDwalletIx(
   guardProgramId: PublicKey,
   params: {
-    principal: PublicKey;
+    authority: PublicKey;
     guardedDwallet: PublicKey;
-    humanProfile: PublicKey;
-    agent: PublicKey;
-    humanrailCapability: PublicKey;
     dwallet: PublicKey;
-    allowedChainId: number;
-    allowedAssetHash: Uint8Array;
-    allowedRecipientHash: Uint8Array;
-    perTxLimit: bigint;
-    dailyLimit: bigint;
-    totalLimit: bigint;
-    expiresAt: bigint;
+    cpiAuthority: PublicKey;
   }
 ): TransactionInstruction {
-  const data = Buffer.alloc(8 + 4 + 32 + 32 + 8 + 8 + 8 + 8);
-  let offset = 0;
-  DISCRIMINATORS.initializeGuardedDwalletDemo.copy(data, offset);
-  offset += 8;
-  data.writeUInt32LE(params.allowedChainId, offset);
-  offset += 4;
-  Buffer.from(params.allowedAssetHash).copy(data, offset);
-  offset += 32;
-  Buffer.from(params.allowedRecipientHash).copy(data, offset);
-  offset += 32;
-  const view = new DataView(data.buffer, data.byteOffset + offset, 32);
-  view.setBigUint64(0, params.perTxLimit, true);
-  view.setBigUint64(8, params.dailyLimit, true);
-  view.setBigUint64(16, params.totalLimit, true);
-  view.setBigInt64(24, params.expiresAt, true);
-
   return new TransactionInstruction({
     keys: [
-      { pubkey: params.principal, isSigner: true, isWritable: true },
+      { pubkey: params.authority, isSigner: true, isWritable: false },
       { pubkey: params.guardedDwallet, isSigner: false, isWritable: true },
-      { pubkey: params.humanProfile, isSigner: false, isWritable: false },
-      { pubkey: params.agent, isSigner: false, isWritable: false },
-      { pubkey: params.humanrailCapability, isSigner: false, isWritable: false },
       { pubkey: params.dwallet, isSigner: false, isWritable: false },
-      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      { pubkey: params.cpiAuthority, isSigner: false, isWritable: false },
     ],
     programId: guardProgramId,
-    data,
+    data: DISCRIMINATORS.armGuardedDwallet,
   });
 }
 
 // ---------------------------------------------------------------------------
 // Instruction: freeze_guarded_dwallet
 // Accounts:
-//   0. principal       [signer]
+//   0. authority       [signer] — the principal or a guardian
 //   1. guarded_dwallet [writable]
 // ---------------------------------------------------------------------------
 export function buildFreezeGuardedDwalletIx(
   guardProgramId: PublicKey,
   params: {
-    principal: PublicKey;
+    authority: PublicKey;
     guardedDwallet: PublicKey;
   }
 ): TransactionInstruction {
   return new TransactionInstruction({
     keys: [
-      { pubkey: params.principal, isSigner: true, isWritable: false },
+      { pubkey: params.authority, isSigner: true, isWritable: false },
       { pubkey: params.guardedDwallet, isSigner: false, isWritable: true },
     ],
     programId: guardProgramId,
@@ -188,6 +272,35 @@ export function buildUnfreezeGuardedDwalletIx(
   });
 }
 
+// ---------------------------------------------------------------------------
+// Instruction: set_principal_digests
+// Accounts:
+//   0. principal       [signer]
+//   1. guarded_dwallet [writable]
+// Args: allowed (bool). Turning it on is timelocked when a policy change
+// delay is set; it then has to go through propose / execute.
+// ---------------------------------------------------------------------------
+export function buildSetPrincipalDigestsIx(
+  guardProgramId: PublicKey,
+  params: {
+    principal: PublicKey;
+    guardedDwallet: PublicKey;
+    allowed: boolean;
+  }
+): TransactionInstruction {
+  return new TransactionInstruction({
+    keys: [
+      { pubkey: params.principal, isSigner: true, isWritable: false },
+      { pubkey: params.guardedDwallet, isSigner: false, isWritable: true },
+    ],
+    programId: guardProgramId,
+    data: Buffer.concat([
+      DISCRIMINATORS.setPrincipalDigests,
+      Buffer.from([params.allowed ? 1 : 0]),
+    ]),
+  });
+}
+
 // ---------------------------------------------------------------------------
 // Instruction: approve_guarded_message
 // Accounts (from IDL):
@@ -196,12 +309,15 @@ export function buildUnfreezeGuardedDwalletIx(
 //   2. guard_signing_request  [writable]
 //   3. dwallet
 //   4. agent_registry_account [optional]
-//   5. cpi_authority
-//   6. program
-//   7. dwallet_program
-//   8. coordinator
-//   9. message_approval       [writable]
-//   10. system_program
+//   5. human_profile
+//   6. humanrail_capability   [optional]
+//   7. cpi_authority
+//   8. program
+//   9. dwallet_program
+//   10. coordinator
+//   11. message_approval      [writable]
+//   12. system_program
+// Args: request fields, then the SigningPayload to decode.
 // ---------------------------------------------------------------------------
 export function buildApproveGuardedMessageIx(
   guardProgramId: PublicKey,
@@ -211,6 +327,8 @@ export function buildApproveGuardedMessageIx(
     guardSigningRequest: PublicKey;
     dwallet: PublicKey;
     agentRegistryAccount?: PublicKey | null;
+    humanProfile: PublicKey;
+    humanrailCapability?: PublicKey | null;
     cpiAuthority: PublicKey;
     coordinator: PublicKey;
     messageApproval: PublicKey;
@@ -224,9 +342,11 @@ export function buildApproveGuardedMessageIx(
     userPubkey: Uint8Array;
     signatureScheme: number;
     messageApprovalBump: number;
+    payload: SigningPayload;
   }
 ): TransactionInstruction {
-  const data = Buffer.alloc(8 + 32 + 32 + 32 + 4 + 32 + 32 + 8 + 32 + 2 + 1);
+  const payload = encodeSigningPayload(params.payload);
+  const data = Buffer.alloc(8 + 32 + 32 + 32 + 4 + 32 + 32 + 8 + 32 + 2 + 1 + payload.length);
   let offset = 0;
   DISCRIMINATORS.approveGuardedMessage.copy(data, offset);
   offset += 8;
@@ -250,6 +370,8 @@ export function buildApproveGuardedMessageIx(
   data.writeUInt16LE(params.signatureScheme, offset);
   offset += 2;
   data.writeUInt8(params.messageApprovalBump, offset);
+  offset += 1;
+  payload.copy(data, offset);
 
   const keys = [
     { pubkey: params.requester, isSigner: true, isWritable: true },
@@ -259,6 +381,8 @@ export function buildApproveGuardedMessageIx(
     // Anchor Option<AccountInfo> requires a placeholder when None.
     // Use the program ID as the None placeholder.
     { pubkey: params.agentRegistryAccount ?? guardProgramId, isSigner: false, isWritable: false },
+    { pubkey: params.humanProfile, isSigner: false, isWritable: false },
+    { pubkey: params.humanrailCapability ?? guardProgramId, isSigner: false, isWritable: false },
     { pubkey: params.cpiAuthority, isSigner: false, isWritable: false },
     { pubkey: guardProgramId, isSigner: false, isWritable: false },
     { pubkey: IKA_PROGRAM_ID, isSigner: false, isWritable: false },
@@ -3,8 +3,10 @@
 import { useEffect, useState, useCallback, useMemo } from "react";
 import { useConnection, useWallet } from "@solana/wallet-adapter-react";
 import { PublicKey } from "@solana/web3.js";
+import { BorshCoder, EventParser, Idl } from "@coral-xyz/anchor";
 import { useCluster } from "@/lib/solana/cluster-context";
 import { getProgramId } from "@/lib/programs";
+import dwalletGuardIdl from "@/lib/idl/humanrail_dwallet_guard.json";
 
 // ---------------------------------------------------------------------------
 // Guard program status hook
@@ -70,22 +72,13 @@ export function useDwalletGuard() {
     [guardProgramId]
   );
 
+  // One guard per dWallet: seeds ["guarded_dwallet", dwallet]
   const deriveGuardedDwallet = useCallback(
-    (
-      principal: PublicKey,
-      agent: PublicKey,
-      dwallet: PublicKey,
-      programId?: PublicKey
-    ): [PublicKey, number] => {
+    (dwallet: PublicKey, programId?: PublicKey): [PublicKey, number] => {
       const id = programId ?? guardProgramId;
       if (!id) throw new Error("Guard program ID not configured");
       return PublicKey.findProgramAddressSync(
-        [
-          Buffer.from("guarded_dwallet"),
-          principal.toBuffer(),
-          agent.toBuffer(),
-          dwallet.toBuffer(),
-        ],
+        [Buffer.from("guarded_dwallet"), dwallet.toBuffer()],
         id
       );
     },
@@ -162,27 +155,178 @@ export function useDwalletGuard() {
 }
 
 // ---------------------------------------------------------------------------
-// Raw account parsers (based on IDL account layouts)
+// Events (decoded with the IDL from "Program data:" log lines)
+// ---------------------------------------------------------------------------
+export type GuardEventName =
+  | "GuardedDwalletInitialized"
+  | "Armed"
+  | "Frozen"
+  | "Unfrozen"
+  | "AutoFrozen"
+  | "SigningApproved"
+  | "SigningRejected"
+  | "SigningPendingCosign"
+  | "Cosigned"
+  | "PolicyChanged"
+  | "PrincipalTransferProposed"
+  | "PrincipalTransferred"
+  | "DwalletReleased";
+
+export interface GuardEvent {
+  name: GuardEventName;
+  // Fields as named in the IDL (snake_case); integers are BN, keys PublicKey
+  data: Record<string, unknown>;
+}
+
+const guardCoder = new BorshCoder(dwalletGuardIdl as unknown as Idl);
+
+export function parseGuardEvents(guardProgramId: PublicKey, logs: string[]): GuardEvent[] {
+  const parser = new EventParser(guardProgramId, guardCoder);
+  return Array.from(parser.parseLogs(logs), (event) => ({
+    name: event.name as GuardEventName,
+    data: event.data as Record<string, unknown>,
+  }));
+}
+
 // ---------------------------------------------------------------------------
+// Raw account parsers (Borsh, field order as in the IDL account layouts)
+// ---------------------------------------------------------------------------
+export const GUARDED_DWALLET_VERSION = 2;
+export const GUARD_SIGNING_REQUEST_VERSION = 2;
+
+export enum SigningStatus {
+  Pending = 0,
+  Approved = 1,
+  Rejected = 2,
+  Signed = 3,
+  PendingCosign = 4,
+}
+
+// RejectionReason variants in declaration order; the on-chain value is the index
+export const REJECTION_REASONS = [
+  "None",
+  "Frozen",
+  "Expired",
+  "ChainNotAllowed",
+  "AssetNotAllowed",
+  "RecipientNotAllowed",
+  "InvalidAmount",
+  "PerTxLimitExceeded",
+  "DailyLimitExceeded",
+  "TotalLimitExceeded",
+  "DwalletMismatch",
+  "UnauthorizedPrincipal",
+  "RecipientCapExceeded",
+  "CapabilityInactive",
+  "AgentNotActive",
+  "HourlyLimitExceeded",
+  "WeeklyLimitExceeded",
+  "MonthlyLimitExceeded",
+  "VelocityLimitExceeded",
+  "OutsideTradingWindow",
+  "NotYetActive",
+  "CooldownActive",
+  "CosignersNotConfigured",
+  "AgentPerTxLimitExceeded",
+  "AgentDailyLimitExceeded",
+  "PayloadRequired",
+  "InvalidPayload",
+  "PayloadMismatch",
+  "SignatureSchemeNotAllowed",
+  "InvalidUserPubkey",
+  "NotArmed",
+] as const;
+
+export interface PeriodCounter {
+  spent: bigint;
+  epoch: bigint;
+}
+
+export interface RollingDay {
+  buckets: bigint[];
+  lastHour: bigint;
+}
+
+export interface AssetLimits {
+  perTxLimit: bigint;
+  dailyLimit: bigint;
+  totalLimit: bigint;
+  hourlyLimit: bigint;
+  weeklyLimit: bigint;
+  monthlyLimit: bigint;
+  cosignThreshold: bigint;
+}
+
+export interface AssetPolicy {
+  chainId: number;
+  assetHash: Uint8Array;
+  limits: AssetLimits;
+  hourly: PeriodCounter;
+  daily: PeriodCounter;
+  weekly: PeriodCounter;
+  monthly: PeriodCounter;
+  totalSpent: bigint;
+  rolling: RollingDay;
+}
+
+export interface RecipientEntry {
+  chainId: number;
+  recipientHash: Uint8Array;
+  label: Uint8Array;
+  perTxCap: bigint;
+}
+
+export interface AgentEntry {
+  agent: PublicKey;
+  capability: PublicKey;
+  perTxLimit: bigint;
+  dailyLimit: bigint;
+}
+
+export interface AgentSpend {
+  agent: PublicKey;
+  chainId: number;
+  assetHash: Uint8Array;
+  daily: RollingDay;
+}
+
 export interface GuardedDwallet {
   version: number;
   principal: PublicKey;
   humanProfile: PublicKey;
-  agent: PublicKey;
-  humanrailCapability: PublicKey;
   dwallet: PublicKey;
-  allowedChainId: number;
-  allowedAssetHash: Uint8Array;
-  allowedRecipientHash: Uint8Array;
-  perTxLimit: bigint;
-  dailyLimit: bigint;
-  totalLimit: bigint;
-  dailySpent: bigint;
-  totalSpent: bigint;
-  lastSpendDay: bigint;
   expiresAt: bigint;
   frozen: boolean;
   bump: number;
+  policyNonce: bigint;
+  policyChangeDelay: bigint;
+  outstandingApprovals: number;
+  pendingCosigns: number;
+  demoMode: boolean;
+  // 0 = CalendarDay, 1 = Rolling24h
+  dailyWindow: number;
+  velocity: { maxApprovalsPerHour: number; maxConsecutiveRejections: number };
+  hourlyApprovals: PeriodCounter;
+  consecutiveRejections: number;
+  tradingWindow: { allowedHours: number; allowedWeekdays: number; utcOffsetSecs: number };
+  notBefore: bigint;
+  minIntervalSecs: bigint;
+  lastApprovalAt: bigint;
+  cosignRequired: number;
+  pendingPrincipal: PublicKey;
+  curve: number;
+  allowedSchemes: number;
+  principalDigests: boolean;
+  armed: boolean;
+  preArmAuthority: PublicKey;
+  releaseAuthority: PublicKey;
+  allowedChainIds: number[];
+  assets: AssetPolicy[];
+  recipients: RecipientEntry[];
+  cosigners: PublicKey[];
+  agents: AgentEntry[];
+  agentSpend: AgentSpend[];
+  guardians: PublicKey[];
 }
 
 export interface GuardSigningRequest {
@@ -199,90 +343,164 @@ export interface GuardSigningRequest {
   recipientHash: Uint8Array;
   amount: bigint;
   signatureScheme: number;
-  status: number;
-  rejectionCode: number;
+  status: SigningStatus;
+  // Index into REJECTION_REASONS
+  rejectionReason: number;
   ikaMessageApproval: PublicKey;
   createdAt: bigint;
   bump: number;
+  payer: PublicKey;
+  userPubkey: Uint8Array;
+  messageApprovalBump: number;
+  cosignedBy: PublicKey[];
 }
 
-function parseGuardedDwallet(data: Buffer): GuardedDwallet | null {
-  try {
-    if (data.length < 295) return null;
-    let offset = 8; // skip discriminator
-
-    const version = data[offset];
-    offset += 1;
-
-    const principal = new PublicKey(data.slice(offset, offset + 32));
-    offset += 32;
+class BorshReader {
+  private offset = 8; // skip discriminator
 
-    const humanProfile = new PublicKey(data.slice(offset, offset + 32));
-    offset += 32;
+  constructor(private readonly data: Buffer) {}
 
-    const agent = new PublicKey(data.slice(offset, offset + 32));
-    offset += 32;
-
-    const humanrailCapability = new PublicKey(data.slice(offset, offset + 32));
-    offset += 32;
-
-    const dwallet = new PublicKey(data.slice(offset, offset + 32));
-    offset += 32;
+  u8(): number {
+    const value = this.data.readUInt8(this.offset);
+    this.offset += 1;
+    return value;
+  }
 
-    const allowedChainId = data.readUInt32LE(offset);
-    offset += 4;
+  bool(): boolean {
+    return this.u8() === 1;
+  }
 
-    const allowedAssetHash = new Uint8Array(data.slice(offset, offset + 32));
-    offset += 32;
+  u16(): number {
+    const value = this.data.readUInt16LE(this.offset);
+    this.offset += 2;
+    return value;
+  }
 
-    const allowedRecipientHash = new Uint8Array(data.slice(offset, offset + 32));
-    offset += 32;
+  u32(): number {
+    const value = this.data.readUInt32LE(this.offset);
+    this.offset += 4;
+    return value;
+  }
 
-    const perTxLimit = data.readBigUInt64LE(offset);
-    offset += 8;
+  i32(): number {
+    const value = this.data.readInt32LE(this.offset);
+    this.offset += 4;
+    return value;
+  }
 
-    const dailyLimit = data.readBigUInt64LE(offset);
-    offset += 8;
+  u64(): bigint {
+    const value = this.data.readBigUInt64LE(this.offset);
+    this.offset += 8;
+    return value;
+  }
 
-    const totalLimit = data.readBigUInt64LE(offset);
-    offset += 8;
+  i64(): bigint {
+    const value = this.data.readBigInt64LE(this.offset);
+    this.offset += 8;
+    return value;
+  }
 
-    const dailySpent = data.readBigUInt64LE(offset);
-    offset += 8;
+  bytes(len: number): Uint8Array {
+    if (this.offset + len > this.data.length) throw new RangeError("account data too short");
+    const value = new Uint8Array(this.data.slice(this.offset, this.offset + len));
+    this.offset += len;
+    return value;
+  }
 
-    const totalSpent = data.readBigUInt64LE(offset);
-    offset += 8;
+  pubkey(): PublicKey {
+    return new PublicKey(this.bytes(32));
+  }
 
-    const lastSpendDay = data.readBigInt64LE(offset);
-    offset += 8;
+  vec<T>(item: () => T): T[] {
+    const len = this.u32();
+    return Array.from({ length: len }, item);
+  }
 
-    const expiresAt = data.readBigInt64LE(offset);
-    offset += 8;
+  periodCounter(): PeriodCounter {
+    return { spent: this.u64(), epoch: this.i64() };
+  }
 
-    const frozen = data[offset] === 1;
-    offset += 1;
+  rollingDay(): RollingDay {
+    return { buckets: Array.from({ length: 24 }, () => this.u64()), lastHour: this.i64() };
+  }
+}
 
-    const bump = data[offset];
+function parseGuardedDwallet(data: Buffer): GuardedDwallet | null {
+  try {
+    const r = new BorshReader(data);
+    const version = r.u8();
+    // Version 1 guards use the old per-agent layout and are not supported
+    if (version !== GUARDED_DWALLET_VERSION) return null;
 
     return {
       version,
-      principal,
-      humanProfile,
-      agent,
-      humanrailCapability,
-      dwallet,
-      allowedChainId,
-      allowedAssetHash,
-      allowedRecipientHash,
-      perTxLimit,
-      dailyLimit,
-      totalLimit,
-      dailySpent,
-      totalSpent,
-      lastSpendDay,
-      expiresAt,
-      frozen,
-      bump,
+      principal: r.pubkey(),
+      humanProfile: r.pubkey(),
+      dwallet: r.pubkey(),
+      expiresAt: r.i64(),
+      frozen: r.bool(),
+      bump: r.u8(),
+      policyNonce: r.u64(),
+      policyChangeDelay: r.i64(),
+      outstandingApprovals: r.u32(),
+      pendingCosigns: r.u32(),
+      demoMode: r.bool(),
+      dailyWindow: r.u8(),
+      velocity: { maxApprovalsPerHour: r.u32(), maxConsecutiveRejections: r.u32() },
+      hourlyApprovals: r.periodCounter(),
+      consecutiveRejections: r.u32(),
+      tradingWindow: { allowedHours: r.u32(), allowedWeekdays: r.u8(), utcOffsetSecs: r.i32() },
+      notBefore: r.i64(),
+      minIntervalSecs: r.i64(),
+      lastApprovalAt: r.i64(),
+      cosignRequired: r.u8(),
+      pendingPrincipal: r.pubkey(),
+      curve: r.u16(),
+      allowedSchemes: r.u8(),
+      principalDigests: r.bool(),
+      armed: r.bool(),
+      preArmAuthority: r.pubkey(),
+      releaseAuthority: r.pubkey(),
+      allowedChainIds: r.vec(() => r.u32()),
+      assets: r.vec(() => ({
+        chainId: r.u32(),
+        assetHash: r.bytes(32),
+        limits: {
+          perTxLimit: r.u64(),
+          dailyLimit: r.u64(),
+          totalLimit: r.u64(),
+          hourlyLimit: r.u64(),
+          weeklyLimit: r.u64(),
+          monthlyLimit: r.u64(),
+          cosignThreshold: r.u64(),
+        },
+        hourly: r.periodCounter(),
+        daily: r.periodCounter(),
+        weekly: r.periodCounter(),
+        monthly: r.periodCounter(),
+        totalSpent: r.u64(),
+        rolling: r.rollingDay(),
+      })),
+      recipients: r.vec(() => ({
+        chainId: r.u32(),
+        recipientHash: r.bytes(32),
+        label: r.bytes(32),
+        perTxCap: r.u64(),
+      })),
+      cosigners: r.vec(() => r.pubkey()),
+      agents: r.vec(() => ({
+        agent: r.pubkey(),
+        capability: r.pubkey(),
+        perTxLimit: r.u64(),
+        dailyLimit: r.u64(),
+      })),
+      agentSpend: r.vec(() => ({
+        agent: r.pubkey(),
+        chainId: r.u32(),
+        assetHash: r.bytes(32),
+        daily: r.rollingDay(),
+      })),
+      guardians: r.vec(() => r.pubkey()),
     };
   } catch (e) {
     console.error("Failed to parse GuardedDwallet:", e);
@@ -292,81 +510,33 @@ function parseGuardedDwallet(data: Buffer): GuardedDwallet | null {
 
 function parseGuardSigningRequest(data: Buffer): GuardSigningRequest | null {
   try {
-    if (data.length < 387) return null;
-    let offset = 8; // skip discriminator
-
-    const version = data[offset];
-    offset += 1;
-
-    const requestId = new Uint8Array(data.slice(offset, offset + 32));
-    offset += 32;
-
-    const guardedDwallet = new PublicKey(data.slice(offset, offset + 32));
-    offset += 32;
-
-    const principal = new PublicKey(data.slice(offset, offset + 32));
-    offset += 32;
-
-    const agent = new PublicKey(data.slice(offset, offset + 32));
-    offset += 32;
-
-    const dwallet = new PublicKey(data.slice(offset, offset + 32));
-    offset += 32;
-
-    const messageDigest = new Uint8Array(data.slice(offset, offset + 32));
-    offset += 32;
-
-    const messageMetadataDigest = new Uint8Array(data.slice(offset, offset + 32));
-    offset += 32;
-
-    const destinationChainId = data.readUInt32LE(offset);
-    offset += 4;
-
-    const assetHash = new Uint8Array(data.slice(offset, offset + 32));
-    offset += 32;
-
-    const recipientHash = new Uint8Array(data.slice(offset, offset + 32));
-    offset += 32;
-
-    const amount = data.readBigUInt64LE(offset);
-    offset += 8;
-
-    const signatureScheme = data.readUInt16LE(offset);
-    offset += 2;
-
-    const status = data[offset];
-    offset += 1;
-
-    const rejectionCode = data.readUInt16LE(offset);
-    offset += 2;
-
-    const ikaMessageApproval = new PublicKey(data.slice(offset, offset + 32));
-    offset += 32;
-
-    const createdAt = data.readBigInt64LE(offset);
-    offset += 8;
-
-    const bump = data[offset];
+    const r = new BorshReader(data);
+    const version = r.u8();
+    if (version !== GUARD_SIGNING_REQUEST_VERSION) return null;
 
     return {
       version,
-      requestId,
-      guardedDwallet,
-      principal,
-      agent,
-      dwallet,
-      messageDigest,
-      messageMetadataDigest,
-      destinationChainId,
-      assetHash,
-      recipientHash,
-      amount,
-      signatureScheme,
-      status,
-      rejectionCode,
-      ikaMessageApproval,
-      createdAt,
-      bump,
+      requestId: r.bytes(32),
+      guardedDwallet: r.pubkey(),
+      principal: r.pubkey(),
+      agent: r.pubkey(),
+      dwallet: r.pubkey(),
+      messageDigest: r.bytes(32),
+      messageMetadataDigest: r.bytes(32),
+      destinationChainId: r.u32(),
+      assetHash: r.bytes(32),
+      recipientHash: r.bytes(32),
+      amount: r.u64(),
+      signatureScheme: r.u16(),
+      status: r.u8(),
+      rejectionReason: r.u8(),
+      ikaMessageApproval: r.pubkey(),
+      createdAt: r.i64(),
+      bump: r.u8(),
+      payer: r.pubkey(),
+      userPubkey: r.bytes(32),
+      messageApprovalBump: r.u8(),
+      cosignedBy: r.vec(() => r.pubkey()),
     };
   } catch (e) {
     console.error("Failed to parse GuardSigningRequest:", e);
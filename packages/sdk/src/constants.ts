@@ -160,20 +160,15 @@ export function deriveDwalletGuardCpiAuthority(programId?: PublicKey): [PublicKe
   return PublicKey.findProgramAddressSync([Buffer.from("__ika_cpi_authority")], id);
 }
 
-/** Derive the GuardedDwallet PDA.
- *  Seeds: ["guarded_dwallet", principal, agent, dwallet]
+/** Derive the GuardedDwallet PDA, one per dWallet.
+ *  Seeds: ["guarded_dwallet", dwallet]
  */
 export function deriveGuardedDwalletPda(
-  principal: PublicKey,
-  agent: PublicKey,
   dwallet: PublicKey,
   programId?: PublicKey
 ): [PublicKey, number] {
   const id = programId ?? getDwalletGuardProgramId();
-  return PublicKey.findProgramAddressSync(
-    [Buffer.from("guarded_dwallet"), principal.toBuffer(), agent.toBuffer(), dwallet.toBuffer()],
-    id
-  );
+  return PublicKey.findProgramAddressSync([Buffer.from("guarded_dwallet"), dwallet.toBuffer()], id);
 }
 
 /** Derive the GuardSigningRequest PDA.
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::state::RejectionReason;
 
 #[error_code]
 pub enum GuardError {
@@ -28,14 +29,219 @@ pub enum GuardError {
     InvalidLimitConfig = 12,
     #[msg("Expiry must be in the future")]
     InvalidExpiry = 13,
-    #[msg("Human profile owner mismatch")]
+    #[msg("Human profile does not belong to the principal")]
     InvalidHumanProfile = 14,
-    #[msg("Agent owner mismatch")]
+    #[msg("Agent is not active or not owned by the principal")]
     InvalidAgent = 15,
-    #[msg("Capability owner mismatch")]
+    #[msg("Capability is not an active signing delegation from principal to agent")]
     InvalidCapability = 16,
     #[msg("Ika CPI failed — replace with official ika-dwallet crate")]
     IkaCpiFailed = 17,
     #[msg("Invalid request — missing agent registry account for agent signer")]
     MissingAgentRegistryAccount = 18,
+    #[msg("Recipient per-transaction cap exceeded")]
+    RecipientCapExceeded = 19,
+    #[msg("Recipient is already allowlisted")]
+    RecipientAlreadyAllowed = 20,
+    #[msg("Recipient allowlist is full")]
+    RecipientListFull = 21,
+    #[msg("Asset policy already exists")]
+    AssetPolicyAlreadyExists = 22,
+    #[msg("Asset policy list is full")]
+    AssetPolicyListFull = 23,
+    #[msg("Destination chain is already allowed")]
+    ChainAlreadyAllowed = 24,
+    #[msg("Allowed chain list is full")]
+    ChainListFull = 25,
+    #[msg("Loosening policy changes must be proposed and wait for the timelock")]
+    PolicyChangeTimelocked = 26,
+    #[msg("Policy change timelock has not elapsed")]
+    PolicyChangeNotReady = 27,
+    #[msg("Invalid policy change delay")]
+    InvalidPolicyChangeDelay = 28,
+    #[msg("Signing request is still within its retention window")]
+    SigningRequestRetained = 29,
+    #[msg("Guarded dWallet must be frozen first")]
+    NotFrozen = 30,
+    #[msg("Guard still holds the dWallet authority")]
+    DwalletAuthorityStillHeld = 31,
+    #[msg("Approved signing requests are still awaiting an Ika signature")]
+    OutstandingApprovals = 32,
+    #[msg("Signing request is not in the approved state")]
+    SigningRequestNotApproved = 33,
+    #[msg("Ika MessageApproval has not been signed")]
+    MessageApprovalNotSigned = 34,
+    #[msg("HumanRail capability is revoked, expired or no longer active")]
+    CapabilityInactive = 35,
+    #[msg("Agent is suspended or revoked in the Agent Registry")]
+    AgentNotActive = 36,
+    #[msg("Hourly limit exceeded")]
+    HourlyLimitExceeded = 37,
+    #[msg("Weekly limit exceeded")]
+    WeeklyLimitExceeded = 38,
+    #[msg("Monthly limit exceeded")]
+    MonthlyLimitExceeded = 39,
+    #[msg("Too many approvals this hour")]
+    VelocityLimitExceeded = 40,
+    #[msg("Outside the allowed trading window")]
+    OutsideTradingWindow = 41,
+    #[msg("Invalid trading window configuration")]
+    InvalidTradingWindow = 42,
+    #[msg("Guarded dWallet policy is not active yet")]
+    NotYetActive = 43,
+    #[msg("Cooldown since the last approval has not elapsed")]
+    CooldownActive = 44,
+    #[msg("Not-before time must be earlier than expiry")]
+    InvalidNotBefore = 45,
+    #[msg("Invalid minimum interval between approvals")]
+    InvalidMinInterval = 46,
+    #[msg("Signing request is not waiting for co-signers")]
+    SigningRequestNotPending = 47,
+    #[msg("Signer is not a configured co-signer")]
+    UnauthorizedCosigner = 48,
+    #[msg("Co-signer has already approved this request")]
+    AlreadyCosigned = 49,
+    #[msg("Co-signer is already configured")]
+    CosignerAlreadyAdded = 50,
+    #[msg("Co-signer list is full")]
+    CosignerListFull = 51,
+    #[msg("Co-signer not found")]
+    CosignerNotFound = 52,
+    #[msg("Required co-signer count exceeds configured co-signers")]
+    InvalidCosignRequired = 53,
+    #[msg("Amount needs co-signers but none are configured")]
+    CosignersNotConfigured = 54,
+    #[msg("Agent is already authorized")]
+    AgentAlreadyAdded = 55,
+    #[msg("Agent list is full")]
+    AgentListFull = 56,
+    #[msg("Agent not found")]
+    AgentNotFound = 57,
+    #[msg("Amount exceeds agent per-transaction limit")]
+    AgentPerTxLimitExceeded = 58,
+    #[msg("Agent daily limit exceeded")]
+    AgentDailyLimitExceeded = 59,
+    #[msg("Guardian is already configured")]
+    GuardianAlreadyAdded = 60,
+    #[msg("Guardian list is full")]
+    GuardianListFull = 61,
+    #[msg("Guardian not found")]
+    GuardianNotFound = 62,
+    #[msg("No principal transfer is pending")]
+    NoPendingPrincipalTransfer = 63,
+    #[msg("Signing requests must carry the raw transaction, not a bare digest")]
+    PayloadRequired = 64,
+    #[msg("Payload is not a supported transfer transaction")]
+    InvalidPayload = 65,
+    #[msg("Payload does not match the declared transfer or message digest")]
+    PayloadMismatch = 66,
+    #[msg("Signature scheme is not allowed for this dWallet")]
+    SignatureSchemeNotAllowed = 67,
+    #[msg("User public key is not set")]
+    InvalidUserPubkey = 68,
+    #[msg("Signature schemes must be a non-empty subset of the dWallet curve's schemes")]
+    InvalidSignatureSchemes = 69,
+    #[msg("Account is not an Ika dWallet")]
+    InvalidDwallet = 70,
+    #[msg("dWallet is not active")]
+    DwalletNotActive = 71,
+    #[msg("Guard is not armed: dWallet authority has not been handed to the guard")]
+    NotArmed = 72,
+    #[msg("dWallet authority is not the guard's CPI authority")]
+    DwalletAuthorityNotHeld = 73,
+    #[msg("Ika MessageApproval can still be signed")]
+    MessageApprovalStillOpen = 74,
+    #[msg("Invalid release authority")]
+    InvalidReleaseAuthority = 75,
+    #[msg("dWallet authority is neither the principal nor the guard's CPI authority")]
+    DwalletNotOwnedByPrincipal = 76,
+    #[msg("Only the dWallet's previous authority can arm the guard")]
+    UnauthorizedArmAuthority = 77,
+    #[msg("A policy change is still pending; cancel it first")]
+    PolicyChangePending = 78,
+    #[msg("Signing requests are still waiting for co-signers")]
+    PendingCosigns = 79,
+}
+
+impl TryFrom<RejectionReason> for GuardError {
+    type Error = RejectionReason;
+
+    /// Fails only for `RejectionReason::None`.
+    fn try_from(reason: RejectionReason) -> std::result::Result<Self, Self::Error> {
+        Ok(match reason {
+            RejectionReason::None => return Err(reason),
+            RejectionReason::Frozen => GuardError::Frozen,
+            RejectionReason::Expired => GuardError::Expired,
+            RejectionReason::ChainNotAllowed => GuardError::ChainNotAllowed,
+            RejectionReason::AssetNotAllowed => GuardError::AssetNotAllowed,
+            RejectionReason::RecipientNotAllowed => GuardError::RecipientNotAllowed,
+            RejectionReason::InvalidAmount => GuardError::InvalidAmount,
+            RejectionReason::PerTxLimitExceeded => GuardError::PerTxLimitExceeded,
+            RejectionReason::DailyLimitExceeded => GuardError::DailyLimitExceeded,
+            RejectionReason::TotalLimitExceeded => GuardError::TotalLimitExceeded,
+            RejectionReason::DwalletMismatch => GuardError::DwalletMismatch,
+            RejectionReason::UnauthorizedPrincipal => GuardError::UnauthorizedPrincipal,
+            RejectionReason::RecipientCapExceeded => GuardError::RecipientCapExceeded,
+            RejectionReason::CapabilityInactive => GuardError::CapabilityInactive,
+            RejectionReason::AgentNotActive => GuardError::AgentNotActive,
+            RejectionReason::HourlyLimitExceeded => GuardError::HourlyLimitExceeded,
+            RejectionReason::WeeklyLimitExceeded => GuardError::WeeklyLimitExceeded,
+            RejectionReason::MonthlyLimitExceeded => GuardError::MonthlyLimitExceeded,
+            RejectionReason::VelocityLimitExceeded => GuardError::VelocityLimitExceeded,
+            RejectionReason::OutsideTradingWindow => GuardError::OutsideTradingWindow,
+            RejectionReason::NotYetActive => GuardError::NotYetActive,
+            RejectionReason::CooldownActive => GuardError::CooldownActive,
+            RejectionReason::CosignersNotConfigured => GuardError::CosignersNotConfigured,
+            RejectionReason::AgentPerTxLimitExceeded => GuardError::AgentPerTxLimitExceeded,
+            RejectionReason::AgentDailyLimitExceeded => GuardError::AgentDailyLimitExceeded,
+            RejectionReason::PayloadRequired => GuardError::PayloadRequired,
+            RejectionReason::InvalidPayload => GuardError::InvalidPayload,
+            RejectionReason::PayloadMismatch => GuardError::PayloadMismatch,
+            RejectionReason::SignatureSchemeNotAllowed => GuardError::SignatureSchemeNotAllowed,
+            RejectionReason::InvalidUserPubkey => GuardError::InvalidUserPubkey,
+            RejectionReason::NotArmed => GuardError::NotArmed,
+        })
+    }
+}
+
+impl TryFrom<GuardError> for RejectionReason {
+    type Error = GuardError;
+
+    /// Fails for errors that abort the transaction instead of being
+    /// recorded on a GuardSigningRequest.
+    fn try_from(err: GuardError) -> std::result::Result<Self, Self::Error> {
+        Ok(match err {
+            GuardError::Frozen => RejectionReason::Frozen,
+            GuardError::Expired => RejectionReason::Expired,
+            GuardError::ChainNotAllowed => RejectionReason::ChainNotAllowed,
+            GuardError::AssetNotAllowed => RejectionReason::AssetNotAllowed,
+            GuardError::RecipientNotAllowed => RejectionReason::RecipientNotAllowed,
+            GuardError::InvalidAmount => RejectionReason::InvalidAmount,
+            GuardError::PerTxLimitExceeded => RejectionReason::PerTxLimitExceeded,
+            GuardError::DailyLimitExceeded => RejectionReason::DailyLimitExceeded,
+            GuardError::TotalLimitExceeded => RejectionReason::TotalLimitExceeded,
+            GuardError::DwalletMismatch => RejectionReason::DwalletMismatch,
+            GuardError::UnauthorizedPrincipal => RejectionReason::UnauthorizedPrincipal,
+            GuardError::RecipientCapExceeded => RejectionReason::RecipientCapExceeded,
+            GuardError::CapabilityInactive => RejectionReason::CapabilityInactive,
+            GuardError::AgentNotActive => RejectionReason::AgentNotActive,
+            GuardError::HourlyLimitExceeded => RejectionReason::HourlyLimitExceeded,
+            GuardError::WeeklyLimitExceeded => RejectionReason::WeeklyLimitExceeded,
+            GuardError::MonthlyLimitExceeded => RejectionReason::MonthlyLimitExceeded,
+            GuardError::VelocityLimitExceeded => RejectionReason::VelocityLimitExceeded,
+            GuardError::OutsideTradingWindow => RejectionReason::OutsideTradingWindow,
+            GuardError::NotYetActive => RejectionReason::NotYetActive,
+            GuardError::CooldownActive => RejectionReason::CooldownActive,
+            GuardError::CosignersNotConfigured => RejectionReason::CosignersNotConfigured,
+            GuardError::AgentPerTxLimitExceeded => RejectionReason::AgentPerTxLimitExceeded,
+            GuardError::AgentDailyLimitExceeded => RejectionReason::AgentDailyLimitExceeded,
+            GuardError::PayloadRequired => RejectionReason::PayloadRequired,
+            GuardError::InvalidPayload => RejectionReason::InvalidPayload,
+            GuardError::PayloadMismatch => RejectionReason::PayloadMismatch,
+            GuardError::SignatureSchemeNotAllowed => RejectionReason::SignatureSchemeNotAllowed,
+            GuardError::InvalidUserPubkey => RejectionReason::InvalidUserPubkey,
+            GuardError::NotArmed => RejectionReason::NotArmed,
+            other => return Err(other),
+        })
+    }
 }
@@ -0,0 +1,121 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct GuardedDwalletInitialized {
+    pub guarded_dwallet: Pubkey,
+    pub principal: Pubkey,
+    pub agent: Pubkey,
+    pub dwallet: Pubkey,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+/// `authority` is the principal or the guardian that froze the guard.
+#[event]
+pub struct Frozen {
+    pub guarded_dwallet: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Unfrozen {
+    pub guarded_dwallet: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SigningApproved {
+    pub guarded_dwallet: Pubkey,
+    pub request_id: [u8; 32],
+    pub message_digest: [u8; 32],
+    pub destination_chain_id: u32,
+    pub asset_hash: [u8; 32],
+    pub recipient_hash: [u8; 32],
+    pub amount: u64,
+    pub ika_message_approval: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SigningPendingCosign {
+    pub guarded_dwallet: Pubkey,
+    pub request_id: [u8; 32],
+    pub amount: u64,
+    pub cosign_required: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Cosigned {
+    pub guarded_dwallet: Pubkey,
+    pub request_id: [u8; 32],
+    pub cosigner: Pubkey,
+    pub approvals: u8,
+    pub timestamp: i64,
+}
+
+/// `code` is the GuardError number the request was rejected with.
+#[event]
+pub struct SigningRejected {
+    pub guarded_dwallet: Pubkey,
+    pub request_id: [u8; 32],
+    pub destination_chain_id: u32,
+    pub asset_hash: [u8; 32],
+    pub recipient_hash: [u8; 32],
+    pub amount: u64,
+    pub code: u16,
+    pub timestamp: i64,
+}
+
+/// Emitted when approve freezes the guard after too many consecutive
+/// rejections. `code` is the GuardError of the last rejection.
+#[event]
+pub struct AutoFrozen {
+    pub guarded_dwallet: Pubkey,
+    pub consecutive_rejections: u32,
+    pub code: u16,
+    pub timestamp: i64,
+}
+
+/// Emitted whenever a policy change is applied; `policy_nonce` is the
+/// value after the change.
+#[event]
+pub struct PolicyChanged {
+    pub guarded_dwallet: Pubkey,
+    pub policy_nonce: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DwalletReleased {
+    pub guarded_dwallet: Pubkey,
+    pub dwallet: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+/// `pending_principal` is the default pubkey when a proposal is cancelled.
+#[event]
+pub struct PrincipalTransferProposed {
+    pub guarded_dwallet: Pubkey,
+    pub principal: Pubkey,
+    pub pending_principal: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PrincipalTransferred {
+    pub guarded_dwallet: Pubkey,
+    pub previous_principal: Pubkey,
+    pub new_principal: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when the guard is found to hold the dWallet's authority.
+#[event]
+pub struct Armed {
+    pub guarded_dwallet: Pubkey,
+    pub dwallet: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,242 @@
+//! Read-only views of the HumanRail Human Registry, Agent Registry and
+//! Delegation accounts the guard links to.
+//!
+//! Offsets follow the Anchor layouts in lib/idl/{human_registry,
+//! agent_registry,delegation}.json. Only the fields the guard checks are
+//! read; each view verifies the owner program and account discriminator.
+
+use anchor_lang::prelude::*;
+use crate::error::GuardError;
+use crate::{
+    HUMANRAIL_AGENT_REGISTRY_PROGRAM_ID, HUMANRAIL_DELEGATION_PROGRAM_ID,
+    HUMANRAIL_HUMAN_REGISTRY_PROGRAM_ID,
+};
+
+// HumanProfile layout:
+//   0..8    discriminator
+//   8..40   wallet
+const HUMAN_PROFILE_DISCRIMINATOR: [u8; 8] = [32, 133, 87, 162, 100, 194, 215, 212];
+const HP_WALLET: usize = 8;
+const HP_MIN_LEN: usize = 40;
+
+// AgentProfile layout, version 1 (215 bytes, no padding):
+//   0..8     discriminator
+//   8..40    owner_principal
+//   40..72   signing_key (rotatable by the owner)
+//   168      has_tee_measurement — 0 or 1
+//   169      status — 0 = active, 1 = suspended, 2 = revoked
+const AGENT_PROFILE_DISCRIMINATOR: [u8; 8] = [60, 227, 42, 24, 0, 87, 86, 205];
+const AP_V1_OWNER_PRINCIPAL: usize = 8;
+const AP_V1_SIGNING_KEY: usize = 40;
+const AP_V1_HAS_TEE_MEASUREMENT: usize = 168;
+const AP_V1_STATUS: usize = 169;
+const AP_V1_LEN: usize = 215;
+
+// Capability layout:
+//   0..8      discriminator
+//   8..40     principal
+//   40..72    agent (AgentProfile PDA)
+//   72..80    allowed_programs (scope bitmask)
+//   122..130  valid_from
+//   130..138  expires_at
+//   143       status — 0 = active, then revoked, expired, frozen, disputed
+const CAPABILITY_DISCRIMINATOR: [u8; 8] = [192, 140, 41, 92, 236, 64, 181, 99];
+const CAP_PRINCIPAL: usize = 8;
+const CAP_AGENT: usize = 40;
+const CAP_ALLOWED_PROGRAMS: usize = 72;
+const CAP_VALID_FROM: usize = 122;
+const CAP_EXPIRES_AT: usize = 130;
+const CAP_STATUS: usize = 143;
+const CAP_LEN: usize = 551;
+const CAP_STATUS_ACTIVE: u8 = 0;
+
+/// Scope bit that covers dWallet signing. The Delegation SDK maps every
+/// program outside the HumanRail suite, including this guard, to bit 63.
+pub const SIGNING_SCOPE: u64 = 1 << 63;
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&data[offset..offset + 32]);
+    Pubkey::new_from_array(bytes)
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    i64::from_le_bytes(bytes)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Borrows `info`'s data after checking owner, minimum length and
+/// discriminator, failing with `err` otherwise.
+fn checked_data<'a>(
+    info: &'a AccountInfo,
+    owner: &Pubkey,
+    discriminator: &[u8; 8],
+    min_len: usize,
+    err: GuardError,
+) -> Result<std::cell::Ref<'a, &'a mut [u8]>> {
+    if info.owner != owner {
+        return Err(err.into());
+    }
+    let data = info.try_borrow_data()?;
+    if data.len() < min_len || &data[..8] != discriminator {
+        return Err(err.into());
+    }
+    Ok(data)
+}
+
+/// View of a Human Registry HumanProfile.
+pub struct HumanProfileRecord {
+    pub wallet: Pubkey,
+}
+
+impl HumanProfileRecord {
+    pub fn try_parse(info: &AccountInfo) -> Result<Self> {
+        let data = checked_data(
+            info,
+            &HUMANRAIL_HUMAN_REGISTRY_PROGRAM_ID,
+            &HUMAN_PROFILE_DISCRIMINATOR,
+            HP_MIN_LEN,
+            GuardError::InvalidHumanProfile,
+        )?;
+        Ok(Self {
+            wallet: read_pubkey(&data, HP_WALLET),
+        })
+    }
+}
+
+/// Agent Registry agent status.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AgentStatus {
+    Active,
+    Suspended,
+    Revoked,
+}
+
+impl AgentStatus {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Active),
+            1 => Some(Self::Suspended),
+            2 => Some(Self::Revoked),
+            _ => None,
+        }
+    }
+}
+
+/// View of an Agent Registry AgentProfile.
+///
+/// The registry stores no version byte, so the layout is identified by the
+/// account size; any size other than a known layout's is refused rather
+/// than read at the wrong offsets. `version` records the layout that was
+/// parsed so a future registry layout can be added next to it.
+pub struct AgentRecord {
+    pub version: u8,
+    pub owner_principal: Pubkey,
+    pub signing_key: Pubkey,
+    pub status: AgentStatus,
+}
+
+impl AgentRecord {
+    pub fn try_parse(info: &AccountInfo) -> Result<Self> {
+        let data = checked_data(
+            info,
+            &HUMANRAIL_AGENT_REGISTRY_PROGRAM_ID,
+            &AGENT_PROFILE_DISCRIMINATOR,
+            AP_V1_LEN,
+            GuardError::InvalidAgent,
+        )?;
+        let version = agent_profile_version(data.len()).ok_or(GuardError::InvalidAgent)?;
+        require!(
+            data[AP_V1_HAS_TEE_MEASUREMENT] <= 1,
+            GuardError::InvalidAgent
+        );
+        let status = AgentStatus::from_u8(data[AP_V1_STATUS]).ok_or(GuardError::InvalidAgent)?;
+        Ok(Self {
+            version,
+            owner_principal: read_pubkey(&data, AP_V1_OWNER_PRINCIPAL),
+            signing_key: read_pubkey(&data, AP_V1_SIGNING_KEY),
+            status,
+        })
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == AgentStatus::Active
+    }
+}
+
+/// AgentProfile layout version for an account of `len` bytes.
+fn agent_profile_version(len: usize) -> Option<u8> {
+    match len {
+        AP_V1_LEN => Some(1),
+        _ => None,
+    }
+}
+
+/// View of a Delegation program Capability.
+pub struct CapabilityRecord {
+    pub principal: Pubkey,
+    pub agent: Pubkey,
+    pub allowed_programs: u64,
+    pub valid_from: i64,
+    pub expires_at: i64,
+    pub status: u8,
+}
+
+impl CapabilityRecord {
+    pub fn try_parse(info: &AccountInfo) -> Result<Self> {
+        let data = checked_data(
+            info,
+            &HUMANRAIL_DELEGATION_PROGRAM_ID,
+            &CAPABILITY_DISCRIMINATOR,
+            CAP_LEN,
+            GuardError::InvalidCapability,
+        )?;
+        Ok(Self {
+            principal: read_pubkey(&data, CAP_PRINCIPAL),
+            agent: read_pubkey(&data, CAP_AGENT),
+            allowed_programs: read_u64(&data, CAP_ALLOWED_PROGRAMS),
+            valid_from: read_i64(&data, CAP_VALID_FROM),
+            expires_at: read_i64(&data, CAP_EXPIRES_AT),
+            status: data[CAP_STATUS],
+        })
+    }
+
+    /// Active, inside its validity window at `now`, and scoped for signing.
+    pub fn is_usable(&self, now: i64) -> bool {
+        self.status == CAP_STATUS_ACTIVE
+            && self.valid_from <= now
+            && now < self.expires_at
+            && self.allowed_programs & SIGNING_SCOPE != 0
+    }
+
+    /// Issued by `principal` to `agent`.
+    pub fn links(&self, principal: &Pubkey, agent: &Pubkey) -> bool {
+        &self.principal == principal && &self.agent == agent
+    }
+}
+
+/// HumanRail links an agent needs before it is added to a guard: an active
+/// AgentProfile owned by `principal`, and a usable signing capability from
+/// `principal` to that agent.
+pub fn verify_agent_link(
+    principal: &Pubkey,
+    agent: &AccountInfo,
+    capability: &AccountInfo,
+    now: i64,
+) -> Result<()> {
+    let record = AgentRecord::try_parse(agent)?;
+    require_keys_eq!(record.owner_principal, *principal, GuardError::InvalidAgent);
+    require!(record.is_active(), GuardError::InvalidAgent);
+
+    let capability = CapabilityRecord::try_parse(capability)?;
+    require!(capability.links(principal, agent.key), GuardError::InvalidCapability);
+    require!(capability.is_usable(now), GuardError::InvalidCapability);
+    Ok(())
+}
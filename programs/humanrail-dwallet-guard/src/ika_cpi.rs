@@ -28,6 +28,117 @@ pub const CPI_AUTHORITY_SEED: &[u8] = b"__ika_cpi_authority";
 /// approve_message instruction discriminator.
 const IX_APPROVE_MESSAGE: u8 = 8;
 
+// DWalletSignatureScheme values for approve_message. The network hashes
+// the message with the scheme's hash before signing.
+pub const SCHEME_ECDSA_KECCAK256: u16 = 0;
+pub const SCHEME_ECDSA_SHA256: u16 = 1;
+pub const SCHEME_ECDSA_DOUBLE_SHA256: u16 = 2;
+pub const SCHEME_TAPROOT_SHA256: u16 = 3;
+pub const SCHEME_ECDSA_BLAKE2B256: u16 = 4;
+pub const SCHEME_EDDSA_SHA512: u16 = 5;
+pub const SCHEME_SCHNORRKEL_MERLIN: u16 = 6;
+
+// DWalletCurve values, as stored on the dWallet account.
+pub const CURVE_SECP256K1: u16 = 0;
+pub const CURVE_SECP256R1: u16 = 1;
+pub const CURVE_CURVE25519: u16 = 2;
+pub const CURVE_RISTRETTO: u16 = 3;
+
+/// Bit for `scheme` in a signature scheme mask.
+pub fn scheme_bit(scheme: u16) -> u8 {
+    match scheme {
+        0..=6 => 1 << scheme,
+        _ => 0,
+    }
+}
+
+/// Mask of the signature schemes a dWallet on `curve` can produce, or
+/// `None` for an unknown curve.
+pub fn curve_schemes(curve: u16) -> Option<u8> {
+    let schemes: &[u16] = match curve {
+        CURVE_SECP256K1 => &[
+            SCHEME_ECDSA_KECCAK256,
+            SCHEME_ECDSA_SHA256,
+            SCHEME_ECDSA_DOUBLE_SHA256,
+            SCHEME_TAPROOT_SHA256,
+            SCHEME_ECDSA_BLAKE2B256,
+        ],
+        CURVE_SECP256R1 => &[SCHEME_ECDSA_SHA256],
+        CURVE_CURVE25519 => &[SCHEME_EDDSA_SHA512],
+        CURVE_RISTRETTO => &[SCHEME_SCHNORRKEL_MERLIN],
+        _ => return None,
+    };
+    Some(schemes.iter().fold(0, |mask, &scheme| mask | scheme_bit(scheme)))
+}
+
+// dWallet account layout (153 bytes, see tools/ika-dkg-cli/src/dkg.rs):
+//   0      discriminator (1)
+//   1      version (1)
+//   2..34  authority (32)
+//   34..36 curve u16 LE (2)
+//   36     state (1) — 0 = DKG in progress, 1 = active, 2 = frozen
+//   37     public_key_len (1) — 32 or 33
+//   38..103 public_key, padded (65)
+const DWALLET_DISCRIMINATOR: u8 = 2;
+const DWALLET_LEN: usize = 153;
+const DW_AUTHORITY: usize = 2;
+const DW_CURVE: usize = 34;
+const DW_STATE: usize = 36;
+const DW_STATE_ACTIVE: u8 = 1;
+const DW_PUBLIC_KEY_LEN: usize = 37;
+const DW_PUBLIC_KEY: usize = 38;
+const DW_PUBLIC_KEY_MAX: usize = 65;
+
+// MessageApproval account layout (312 bytes, see lib/ika/parsers.ts):
+//   0      discriminator (1)
+//   172    status (1) — 0 = pending, 1 = signed
+const MESSAGE_APPROVAL_DISCRIMINATOR: u8 = 14;
+const MESSAGE_APPROVAL_LEN: usize = 312;
+const MA_STATUS: usize = 172;
+const MA_STATUS_SIGNED: u8 = 1;
+
+/// Read-only view of an Ika dWallet account.
+pub struct DWalletAccount {
+    pub authority: Pubkey,
+    pub curve: u16,
+    pub state: u8,
+    pub public_key: Vec<u8>,
+}
+
+impl DWalletAccount {
+    /// Parses `info` if it is an Ika-owned dWallet account, `None` otherwise
+    /// (including when devnet has wiped the account).
+    pub fn try_parse(info: &AccountInfo) -> Result<Option<Self>> {
+        if info.owner != &IKA_PROGRAM_ID {
+            return Ok(None);
+        }
+        let data = info.try_borrow_data()?;
+        if data.len() < DWALLET_LEN || data[0] != DWALLET_DISCRIMINATOR {
+            return Ok(None);
+        }
+        let authority = Pubkey::new_from_array(
+            data[DW_AUTHORITY..DW_AUTHORITY + 32]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let curve = u16::from_le_bytes([data[DW_CURVE], data[DW_CURVE + 1]]);
+        let public_key_len = usize::from(data[DW_PUBLIC_KEY_LEN]);
+        if public_key_len > DW_PUBLIC_KEY_MAX {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            authority,
+            curve,
+            state: data[DW_STATE],
+            public_key: data[DW_PUBLIC_KEY..DW_PUBLIC_KEY + public_key_len].to_vec(),
+        }))
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state == DW_STATE_ACTIVE
+    }
+}
+
 /// CPI context for invoking Ika dWallet instructions.
 pub struct DWalletContext<'info> {
     /// The Ika dWallet program account.
@@ -132,3 +243,28 @@ impl<'info> DWalletContext<'info> {
         Ok(())
     }
 }
+
+/// Read-only view of an Ika MessageApproval account.
+pub struct MessageApprovalAccount {
+    pub status: u8,
+}
+
+impl MessageApprovalAccount {
+    /// Parses `info` if it is an Ika-owned MessageApproval account.
+    pub fn try_parse(info: &AccountInfo) -> Result<Option<Self>> {
+        if info.owner != &IKA_PROGRAM_ID {
+            return Ok(None);
+        }
+        let data = info.try_borrow_data()?;
+        if data.len() < MESSAGE_APPROVAL_LEN || data[0] != MESSAGE_APPROVAL_DISCRIMINATOR {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            status: data[MA_STATUS],
+        }))
+    }
+
+    pub fn is_signed(&self) -> bool {
+        self.status == MA_STATUS_SIGNED
+    }
+}
@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+use crate::error::GuardError;
+use crate::humanrail::HumanProfileRecord;
+use crate::events::{PolicyChanged, PrincipalTransferred};
+use crate::AcceptPrincipalTransfer;
+
+/// Completes a transfer started by propose_principal_transfer. The agents
+/// were delegated by the previous principal, whose capabilities no longer
+/// link to the guard, so they are dropped and must be re-added by the new
+/// principal. A release the previous principal had cleared is dropped too,
+/// and an unarmed guard can then only be armed by the new principal.
+/// Co-signers, guardians and the rest of the policy carry over.
+pub fn handler(ctx: Context<AcceptPrincipalTransfer>) -> Result<()> {
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    let new_principal = ctx.accounts.new_principal.key();
+
+    require!(
+        guarded.pending_principal != Pubkey::default(),
+        GuardError::NoPendingPrincipalTransfer
+    );
+    require_keys_eq!(
+        guarded.pending_principal,
+        new_principal,
+        GuardError::UnauthorizedPrincipal
+    );
+    if !guarded.demo_mode {
+        let profile = HumanProfileRecord::try_parse(&ctx.accounts.human_profile)?;
+        require_keys_eq!(profile.wallet, new_principal, GuardError::InvalidHumanProfile);
+    }
+
+    let previous_principal = guarded.principal;
+    guarded.principal = new_principal;
+    guarded.human_profile = ctx.accounts.human_profile.key();
+    guarded.pending_principal = Pubkey::default();
+    guarded.agents.clear();
+    guarded.agent_spend.clear();
+    guarded.release_authority = Pubkey::default();
+    if !guarded.armed {
+        guarded.pre_arm_authority = new_principal;
+    }
+    guarded.bump_policy_nonce();
+
+    let now = Clock::get()?.unix_timestamp;
+    emit!(PrincipalTransferred {
+        guarded_dwallet: guarded.key(),
+        previous_principal,
+        new_principal,
+        timestamp: now,
+    });
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: now,
+    });
+    msg!(
+        "Transferred principal of GuardedDwallet {} from {} to {}",
+        guarded.key(),
+        previous_principal,
+        new_principal
+    );
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use crate::humanrail::verify_agent_link;
+use crate::state::{AgentEntry, PolicyChange};
+use crate::events::PolicyChanged;
+use crate::AddAgent;
+
+/// Same HumanRail link checks as initialize and an executed AddAgent change:
+/// an active agent owned by the principal and a live signing capability
+/// from the principal to it.
+pub fn handler(ctx: Context<AddAgent>, per_tx_limit: u64, daily_limit: u64) -> Result<()> {
+    let clock = Clock::get()?;
+    let principal = ctx.accounts.principal.key();
+
+    verify_agent_link(
+        &principal,
+        &ctx.accounts.agent,
+        &ctx.accounts.humanrail_capability,
+        clock.unix_timestamp,
+    )?;
+
+    let entry = AgentEntry {
+        agent: ctx.accounts.agent.key(),
+        capability: ctx.accounts.humanrail_capability.key(),
+        per_tx_limit,
+        daily_limit,
+    };
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.apply_policy_change_now(&PolicyChange::AddAgent { entry }, clock.unix_timestamp)?;
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: clock.unix_timestamp,
+    });
+    msg!(
+        "Added agent {} to GuardedDwallet {} ({} agents)",
+        entry.agent,
+        guarded.key(),
+        guarded.agents.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+use crate::state::PolicyChange;
+use crate::events::PolicyChanged;
+use crate::AddAllowedChain;
+
+pub fn handler(ctx: Context<AddAllowedChain>, chain_id: u32) -> Result<()> {
+    let clock = Clock::get()?;
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.apply_policy_change_now(&PolicyChange::AddChain { chain_id }, clock.unix_timestamp)?;
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: clock.unix_timestamp,
+    });
+    msg!(
+        "Allowed chain {} on GuardedDwallet {} ({} chains)",
+        chain_id,
+        guarded.key(),
+        guarded.allowed_chain_ids.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+use crate::state::{AssetLimits, PolicyChange};
+use crate::events::PolicyChanged;
+use crate::AddAssetPolicy;
+
+pub fn handler(
+    ctx: Context<AddAssetPolicy>,
+    chain_id: u32,
+    asset_hash: [u8; 32],
+    limits: AssetLimits,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.apply_policy_change_now(
+        &PolicyChange::AddAsset {
+            chain_id,
+            asset_hash,
+            limits,
+        },
+        clock.unix_timestamp,
+    )?;
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: clock.unix_timestamp,
+    });
+    msg!(
+        "Added asset policy to GuardedDwallet {} ({} assets)",
+        guarded.key(),
+        guarded.assets.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+use crate::state::PolicyChange;
+use crate::events::PolicyChanged;
+use crate::AddCosigner;
+
+pub fn handler(ctx: Context<AddCosigner>, cosigner: Pubkey) -> Result<()> {
+    let clock = Clock::get()?;
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.apply_policy_change_now(&PolicyChange::AddCosigner { cosigner }, clock.unix_timestamp)?;
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: clock.unix_timestamp,
+    });
+    msg!(
+        "Added co-signer {} to GuardedDwallet {} ({} co-signers)",
+        cosigner,
+        guarded.key(),
+        guarded.cosigners.len()
+    );
+    Ok(())
+}
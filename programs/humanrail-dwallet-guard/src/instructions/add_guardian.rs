@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+use crate::events::PolicyChanged;
+use crate::AddGuardian;
+
+pub fn handler(ctx: Context<AddGuardian>, guardian: Pubkey) -> Result<()> {
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.add_guardian(guardian)?;
+    guarded.bump_policy_nonce();
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    msg!(
+        "Added guardian {} to GuardedDwallet {} ({} guardians)",
+        guardian,
+        guarded.key(),
+        guarded.guardians.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+use crate::state::{PolicyChange, RecipientEntry};
+use crate::events::PolicyChanged;
+use crate::AddRecipient;
+
+pub fn handler(
+    ctx: Context<AddRecipient>,
+    chain_id: u32,
+    recipient_hash: [u8; 32],
+    label: [u8; 32],
+    per_tx_cap: u64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    let entry = RecipientEntry {
+        chain_id,
+        recipient_hash,
+        label,
+        per_tx_cap,
+    };
+    guarded.apply_policy_change_now(&PolicyChange::AddRecipient { entry }, clock.unix_timestamp)?;
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: clock.unix_timestamp,
+    });
+    msg!(
+        "Added recipient to GuardedDwallet {} ({} allowlisted)",
+        guarded.key(),
+        guarded.recipients.len()
+    );
+    Ok(())
+}
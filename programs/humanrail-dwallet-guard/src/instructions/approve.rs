@@ -1,22 +1,45 @@
 use anchor_lang::prelude::*;
-use crate::state::{GuardedDwallet, GuardSigningRequest};
-use crate::error::GuardError;
+use crate::state::{
+    AgentEntry, GuardedDwallet, GuardSigningRequest, RejectionReason, SigningStatus, SpendPeriod,
+};
+use crate::events::{AutoFrozen, SigningApproved, SigningPendingCosign, SigningRejected};
+use crate::humanrail::{AgentRecord, CapabilityRecord, HumanProfileRecord};
+use crate::ika_cpi::{scheme_bit, DWalletAccount};
+use crate::payload::{SigningPayload, Transfer};
 use crate::ApproveGuardedMessage;
-use crate::HUMANRAIL_AGENT_REGISTRY_PROGRAM_ID;
+
+/// The request as declared by the caller, in instruction data order.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct ApproveGuardedMessageArgs {
+    pub request_id: [u8; 32],
+    pub message_digest: [u8; 32],
+    pub message_metadata_digest: [u8; 32],
+    pub destination_chain_id: u32,
+    pub asset_hash: [u8; 32],
+    pub recipient_hash: [u8; 32],
+    pub amount: u64,
+    pub user_pubkey: [u8; 32],
+    pub signature_scheme: u16,
+    pub message_approval_bump: u8,
+}
 
 pub fn handler(
     ctx: Context<ApproveGuardedMessage>,
-    request_id: [u8; 32],
-    message_digest: [u8; 32],
-    message_metadata_digest: [u8; 32],
-    destination_chain_id: u32,
-    asset_hash: [u8; 32],
-    recipient_hash: [u8; 32],
-    amount: u64,
-    user_pubkey: [u8; 32],
-    signature_scheme: u16,
-    message_approval_bump: u8,
+    args: ApproveGuardedMessageArgs,
+    payload: SigningPayload,
 ) -> Result<()> {
+    let ApproveGuardedMessageArgs {
+        request_id,
+        message_digest,
+        message_metadata_digest,
+        destination_chain_id,
+        asset_hash,
+        recipient_hash,
+        amount,
+        user_pubkey,
+        signature_scheme,
+        message_approval_bump,
+    } = args;
     let guarded = &mut ctx.accounts.guarded_dwallet;
     let request = &mut ctx.accounts.guard_signing_request;
     let clock = Clock::get()?;
@@ -28,42 +51,43 @@ pub fn handler(
     let requester_key = ctx.accounts.requester.key();
     let is_principal = requester_key == guarded.principal;
 
-    let is_agent = if let Some(agent_account) = &ctx.accounts.agent_registry_account {
-        if agent_account.owner != &HUMANRAIL_AGENT_REGISTRY_PROGRAM_ID {
-            false
-        } else {
-            let data = agent_account.try_borrow_data()?;
-            if data.len() < 72 {
-                false
-            } else {
-                let signing_key = Pubkey::new_from_array(
-                    data[40..72].try_into().map_err(|_| GuardError::InvalidAgent)?
-                );
-                requester_key == signing_key
+    init_request(request, guarded, &args, now, ctx.bumps.guard_signing_request);
+
+    // The requester pays rent on every path, so it is refunded on close.
+    // The Ika inputs are kept so a co-signed request can be sent later.
+    request.payer = requester_key;
+    request.user_pubkey = user_pubkey;
+    request.message_approval_bump = message_approval_bump;
+
+    // An agent signs with whatever key the Agent Registry currently holds
+    // for one of guarded.agents, so key rotation there takes effect
+    // immediately.
+    let agent_record = match &ctx.accounts.agent_registry_account {
+        Some(info) => match (guarded.find_agent(info.key), AgentRecord::try_parse(info)) {
+            (Some(entry), Ok(record)) => Some((*entry, record)),
+            _ => None,
+        },
+        None => None,
+    };
+    let (agent, signer_rejection) = if is_principal {
+        (None, RejectionReason::None)
+    } else {
+        match agent_record {
+            Some((entry, record)) if record.signing_key == requester_key => {
+                if record.is_active() {
+                    (Some(entry), RejectionReason::None)
+                } else {
+                    (Some(entry), RejectionReason::AgentNotActive)
+                }
             }
+            _ => (None, RejectionReason::UnauthorizedPrincipal),
         }
-    } else {
-        false
     };
+    request.agent = agent.map(|entry| entry.agent).unwrap_or_default();
 
-    if !is_principal && !is_agent {
-        init_request(
-            request,
-            guarded,
-            request_id,
-            message_digest,
-            message_metadata_digest,
-            destination_chain_id,
-            asset_hash,
-            recipient_hash,
-            amount,
-            signature_scheme,
-            now,
-            2, // rejected
-            11, // unauthorized_principal
-            ctx.bumps.guard_signing_request,
-            &Pubkey::default(),
-        )?;
+    if signer_rejection != RejectionReason::None {
+        decide(request, SigningStatus::Rejected, signer_rejection, &Pubkey::default());
+        emit_rejected(request, now);
         return Ok(());
     }
 
@@ -71,129 +95,122 @@ pub fn handler(
     // 2. dWallet match
     // ------------------------------------------------------------------
     if ctx.accounts.dwallet.key() != guarded.dwallet {
-        init_request(
+        decide(
             request,
-            guarded,
-            request_id,
-            message_digest,
-            message_metadata_digest,
-            destination_chain_id,
-            asset_hash,
-            recipient_hash,
-            amount,
-            signature_scheme,
-            now,
-            2, // rejected
-            10, // dwallet_mismatch
-            ctx.bumps.guard_signing_request,
+            SigningStatus::Rejected,
+            RejectionReason::DwalletMismatch,
             &Pubkey::default(),
-        )?;
+        );
+        emit_rejected(request, now);
+        note_rejection(guarded, RejectionReason::DwalletMismatch, now);
         return Ok(());
     }
 
     // ------------------------------------------------------------------
-    // 3. Policy checks
+    // 3. Delegation, signing parameter, payload and policy checks
     // ------------------------------------------------------------------
-    let mut rejection_code: u16 = 0;
-
-    if guarded.frozen {
-        rejection_code = 1; // frozen
-    } else if now > guarded.expires_at {
-        rejection_code = 2; // expired
-    } else if destination_chain_id != guarded.allowed_chain_id {
-        rejection_code = 3; // chain_not_allowed
-    } else if asset_hash != guarded.allowed_asset_hash {
-        rejection_code = 4; // asset_not_allowed
-    } else if recipient_hash != guarded.allowed_recipient_hash {
-        rejection_code = 5; // recipient_not_allowed
-    } else if amount == 0 {
-        rejection_code = 6; // invalid_amount
-    } else if amount > guarded.per_tx_limit {
-        rejection_code = 7; // per_tx_limit_exceeded
+    let delegation_live = guarded.demo_mode
+        || delegation_is_live(
+            guarded,
+            &ctx.accounts.human_profile,
+            ctx.accounts.humanrail_capability.as_deref(),
+            agent.as_ref(),
+            now,
+        );
+    let declared = Transfer {
+        chain_id: destination_chain_id,
+        asset_hash,
+        recipient_hash,
+        amount,
+    };
+    let dwallet_public_key = DWalletAccount::try_parse(&ctx.accounts.dwallet)?
+        .map(|dwallet| dwallet.public_key)
+        .unwrap_or_default();
+    // The principal may opt into bare digests; the declared transfer is
+    // then taken at its word, so enabling them is a timelocked change.
+    let payload_rejection = match payload {
+        SigningPayload::Digest if is_principal && guarded.principal_digests => {
+            RejectionReason::None
+        }
+        _ => payload.check(&message_digest, signature_scheme, &declared, &dwallet_public_key),
+    };
+    let signing_rejection = check_signing_params(guarded, signature_scheme, &user_pubkey);
+    let rejection_reason = if !delegation_live {
+        RejectionReason::CapabilityInactive
+    } else if signing_rejection != RejectionReason::None {
+        signing_rejection
+    } else if payload_rejection != RejectionReason::None {
+        payload_rejection
     } else {
-        // Daily reset + daily/total limit check
-        let current_day = now / 86400;
-        let effective_daily_spent = if current_day != guarded.last_spend_day {
-            0u64
-        } else {
-            guarded.daily_spent
-        };
+        check_policy(
+            guarded,
+            agent.as_ref(),
+            now,
+            destination_chain_id,
+            &asset_hash,
+            &recipient_hash,
+            amount,
+        )
+    };
 
-        if effective_daily_spent + amount > guarded.daily_limit {
-            rejection_code = 8; // daily_limit_exceeded
-        } else if guarded.total_limit > 0 && guarded.total_spent + amount > guarded.total_limit {
-            rejection_code = 9; // total_limit_exceeded
-        }
+    // ------------------------------------------------------------------
+    // 4. Rejection path
+    // ------------------------------------------------------------------
+    if rejection_reason != RejectionReason::None {
+        decide(request, SigningStatus::Rejected, rejection_reason, &Pubkey::default());
+        emit_rejected(request, now);
+        note_rejection(guarded, rejection_reason, now);
+        msg!(
+            "Rejected signing request {:?}: {:?}",
+            request_id,
+            rejection_reason
+        );
+        return Ok(());
     }
 
     // ------------------------------------------------------------------
-    // 4. Rejection path
+    // 5. Large transfers wait for co-signers
     // ------------------------------------------------------------------
-    if rejection_code != 0 {
-        init_request(
-            request,
-            guarded,
+    let message_approval_key = ctx.accounts.message_approval.key();
+
+    if needs_cosign(guarded, destination_chain_id, &asset_hash, amount) {
+        decide(request, SigningStatus::PendingCosign, RejectionReason::None, &message_approval_key);
+        guarded.pending_cosigns = guarded.pending_cosigns.saturating_add(1);
+        emit!(SigningPendingCosign {
+            guarded_dwallet: request.guarded_dwallet,
             request_id,
-            message_digest,
-            message_metadata_digest,
-            destination_chain_id,
-            asset_hash,
-            recipient_hash,
             amount,
-            signature_scheme,
-            now,
-            2, // rejected
-            rejection_code,
-            ctx.bumps.guard_signing_request,
-            &Pubkey::default(),
-        )?;
+            cosign_required: guarded.cosign_required,
+            timestamp: now,
+        });
         msg!(
-            "Rejected signing request {:?} with code {}",
+            "Signing request {:?} is waiting for {} co-signers",
             request_id,
-            rejection_code
+            guarded.cosign_required
         );
         return Ok(());
     }
 
     // ------------------------------------------------------------------
-    // 5. Approval path — update spend counters
+    // 6. Approval path — update spend counters
     // ------------------------------------------------------------------
-    let current_day = now / 86400;
-    if current_day != guarded.last_spend_day {
-        guarded.daily_spent = amount;
-        guarded.last_spend_day = current_day;
-    } else {
-        guarded.daily_spent += amount;
-    }
-    guarded.total_spent += amount;
-
-    let message_approval_key = ctx.accounts.message_approval.key();
-
-    init_request(
-        request,
-        guarded,
-        request_id,
-        message_digest,
-        message_metadata_digest,
+    guarded.record_approval(
+        agent.as_ref().map(|entry| &entry.agent),
         destination_chain_id,
-        asset_hash,
-        recipient_hash,
+        &asset_hash,
         amount,
-        signature_scheme,
         now,
-        1, // approved
-        0, // none
-        ctx.bumps.guard_signing_request,
-        &message_approval_key,
     )?;
 
+    decide(request, SigningStatus::Approved, RejectionReason::None, &message_approval_key);
+
     // ------------------------------------------------------------------
-    // 6. CPI to Ika via official ika-dwallet-anchor crate
+    // 7. CPI to Ika via official ika-dwallet-anchor crate
     // ------------------------------------------------------------------
     let dwallet_ctx = crate::ika_cpi::DWalletContext {
-        dwallet_program: ctx.accounts.dwallet_program.clone(),
-        cpi_authority: ctx.accounts.cpi_authority.clone(),
-        caller_program: ctx.accounts.program.clone(),
+        dwallet_program: ctx.accounts.dwallet_program.to_account_info(),
+        cpi_authority: ctx.accounts.cpi_authority.to_account_info(),
+        caller_program: ctx.accounts.program.to_account_info(),
         cpi_authority_bump: ctx.bumps.cpi_authority,
     };
 
@@ -210,6 +227,8 @@ pub fn handler(
         message_approval_bump,
     )?;
 
+    emit_approved(request, now);
+
     msg!(
         "Approved signing request {:?} and CPI'd Ika approve_message via new-format CPI",
         request_id
@@ -219,42 +238,248 @@ pub fn handler(
 }
 
 // ------------------------------------------------------------------
-// Helper to populate a GuardSigningRequest
+// HumanRail delegation — when an agent signs, the principal's capability
+// for that agent must still be active, in its validity window and scoped
+// for signing
+// ------------------------------------------------------------------
+pub fn delegation_is_live(
+    guarded: &GuardedDwallet,
+    human_profile: &AccountInfo,
+    capability: Option<&AccountInfo>,
+    agent: Option<&AgentEntry>,
+    now: i64,
+) -> bool {
+    let profile_ok = match HumanProfileRecord::try_parse(human_profile) {
+        Ok(profile) => profile.wallet == guarded.principal,
+        Err(_) => false,
+    };
+    let capability_ok = match (agent, capability) {
+        (None, _) => true,
+        (Some(entry), Some(info)) if info.key() == entry.capability => {
+            match CapabilityRecord::try_parse(info) {
+                Ok(capability) => {
+                    capability.links(&guarded.principal, &entry.agent) && capability.is_usable(now)
+                }
+                Err(_) => false,
+            }
+        }
+        _ => false,
+    };
+    profile_ok && capability_ok
+}
+
+// ------------------------------------------------------------------
+// Ika signing parameters — the scheme must be one the principal allows
+// (always one the dWallet's curve can produce), and someone must be able
+// to authenticate the Sign request
+// ------------------------------------------------------------------
+pub fn check_signing_params(
+    guarded: &GuardedDwallet,
+    signature_scheme: u16,
+    user_pubkey: &[u8; 32],
+) -> RejectionReason {
+    if guarded.allowed_schemes & scheme_bit(signature_scheme) == 0 {
+        return RejectionReason::SignatureSchemeNotAllowed;
+    }
+    if user_pubkey == &[0u8; 32] {
+        return RejectionReason::InvalidUserPubkey;
+    }
+    RejectionReason::None
+}
+
+// ------------------------------------------------------------------
+// Policy evaluation — returns the rejection reason, or None if approved
+// ------------------------------------------------------------------
+pub fn check_policy(
+    guarded: &GuardedDwallet,
+    agent: Option<&AgentEntry>,
+    now: i64,
+    destination_chain_id: u32,
+    asset_hash: &[u8; 32],
+    recipient_hash: &[u8; 32],
+    amount: u64,
+) -> RejectionReason {
+    if guarded.frozen {
+        return RejectionReason::Frozen;
+    }
+    if !guarded.armed {
+        return RejectionReason::NotArmed;
+    }
+    if now > guarded.expires_at {
+        return RejectionReason::Expired;
+    }
+    if now < guarded.not_before {
+        return RejectionReason::NotYetActive;
+    }
+    if !guarded.trading_window.contains(now) {
+        return RejectionReason::OutsideTradingWindow;
+    }
+    if guarded.last_approval_at > 0
+        && now < guarded.last_approval_at.saturating_add(guarded.min_interval_secs)
+    {
+        return RejectionReason::CooldownActive;
+    }
+    let max_approvals = guarded.velocity.max_approvals_per_hour;
+    if max_approvals > 0
+        && guarded.hourly_approvals.spent_at(SpendPeriod::Hourly, now) >= u64::from(max_approvals)
+    {
+        return RejectionReason::VelocityLimitExceeded;
+    }
+    if !guarded.is_chain_allowed(destination_chain_id) {
+        return RejectionReason::ChainNotAllowed;
+    }
+    let Some(asset) = guarded.find_asset(destination_chain_id, asset_hash) else {
+        return RejectionReason::AssetNotAllowed;
+    };
+    let Some(recipient) = guarded.find_recipient(destination_chain_id, recipient_hash) else {
+        return RejectionReason::RecipientNotAllowed;
+    };
+    if amount == 0 {
+        return RejectionReason::InvalidAmount;
+    }
+    if amount > asset.limits.per_tx_limit {
+        return RejectionReason::PerTxLimitExceeded;
+    }
+    if let Some(agent) = agent {
+        if agent.per_tx_limit > 0 && amount > agent.per_tx_limit {
+            return RejectionReason::AgentPerTxLimitExceeded;
+        }
+    }
+    if recipient.per_tx_cap > 0 && amount > recipient.per_tx_cap {
+        return RejectionReason::RecipientCapExceeded;
+    }
+    if guarded.cosign_required == 0
+        && needs_cosign(guarded, destination_chain_id, asset_hash, amount)
+    {
+        return RejectionReason::CosignersNotConfigured;
+    }
+    for period in SpendPeriod::ALL {
+        let limit = asset.limits.period_limit(period);
+        if limit > 0
+            && asset.spent_at(period, now, guarded.daily_window).saturating_add(amount) > limit
+        {
+            return period.rejection();
+        }
+    }
+    if let Some(agent) = agent {
+        if agent.daily_limit > 0
+            && guarded
+                .agent_daily_spent_at(&agent.agent, destination_chain_id, asset_hash, now)
+                .saturating_add(amount)
+                > agent.daily_limit
+        {
+            return RejectionReason::AgentDailyLimitExceeded;
+        }
+    }
+    if asset.limits.total_limit > 0
+        && asset.total_spent.saturating_add(amount) > asset.limits.total_limit
+    {
+        return RejectionReason::TotalLimitExceeded;
+    }
+    RejectionReason::None
+}
+
+// ------------------------------------------------------------------
+// Count a rejection from an authorized signer; unauthorized signers are
+// not counted so they cannot freeze the guard
+// ------------------------------------------------------------------
+pub fn note_rejection(guarded: &mut Account<GuardedDwallet>, reason: RejectionReason, now: i64) {
+    if guarded.record_rejection() {
+        emit!(AutoFrozen {
+            guarded_dwallet: guarded.key(),
+            consecutive_rejections: guarded.consecutive_rejections,
+            code: reason.code(),
+            timestamp: now,
+        });
+        msg!(
+            "Auto-froze GuardedDwallet {} after {} consecutive rejections",
+            guarded.key(),
+            guarded.consecutive_rejections
+        );
+    }
+}
+
+// ------------------------------------------------------------------
+// Amounts above the asset's co-sign threshold wait for co-signers
+// ------------------------------------------------------------------
+pub fn needs_cosign(
+    guarded: &GuardedDwallet,
+    destination_chain_id: u32,
+    asset_hash: &[u8; 32],
+    amount: u64,
+) -> bool {
+    match guarded.find_asset(destination_chain_id, asset_hash) {
+        Some(asset) => {
+            asset.limits.cosign_threshold > 0 && amount > asset.limits.cosign_threshold
+        }
+        None => false,
+    }
+}
+
+// ------------------------------------------------------------------
+// Emit SigningApproved / SigningRejected from a recorded request
+// ------------------------------------------------------------------
+pub fn emit_approved(request: &GuardSigningRequest, now: i64) {
+    emit!(SigningApproved {
+        guarded_dwallet: request.guarded_dwallet,
+        request_id: request.request_id,
+        message_digest: request.message_digest,
+        destination_chain_id: request.destination_chain_id,
+        asset_hash: request.asset_hash,
+        recipient_hash: request.recipient_hash,
+        amount: request.amount,
+        ika_message_approval: request.ika_message_approval,
+        timestamp: now,
+    });
+}
+
+pub fn emit_rejected(request: &GuardSigningRequest, now: i64) {
+    emit!(SigningRejected {
+        guarded_dwallet: request.guarded_dwallet,
+        request_id: request.request_id,
+        destination_chain_id: request.destination_chain_id,
+        asset_hash: request.asset_hash,
+        recipient_hash: request.recipient_hash,
+        amount: request.amount,
+        code: request.rejection_reason.code(),
+        timestamp: now,
+    });
+}
+
+// ------------------------------------------------------------------
+// Helpers to populate a GuardSigningRequest: the declared request is
+// recorded up front, the outcome once it is decided
 // ------------------------------------------------------------------
 fn init_request(
     request: &mut Account<GuardSigningRequest>,
     guarded: &Account<GuardedDwallet>,
-    request_id: [u8; 32],
-    message_digest: [u8; 32],
-    message_metadata_digest: [u8; 32],
-    destination_chain_id: u32,
-    asset_hash: [u8; 32],
-    recipient_hash: [u8; 32],
-    amount: u64,
-    signature_scheme: u16,
+    args: &ApproveGuardedMessageArgs,
     created_at: i64,
-    status: u8,
-    rejection_code: u16,
     bump: u8,
-    ika_message_approval: &Pubkey,
-) -> Result<()> {
-    request.version = 1;
-    request.request_id = request_id;
+) {
+    request.version = GuardSigningRequest::VERSION;
+    request.request_id = args.request_id;
     request.guarded_dwallet = guarded.key();
     request.principal = guarded.principal;
-    request.agent = guarded.agent;
     request.dwallet = guarded.dwallet;
-    request.message_digest = message_digest;
-    request.message_metadata_digest = message_metadata_digest;
-    request.destination_chain_id = destination_chain_id;
-    request.asset_hash = asset_hash;
-    request.recipient_hash = recipient_hash;
-    request.amount = amount;
-    request.signature_scheme = signature_scheme;
-    request.status = status;
-    request.rejection_code = rejection_code;
-    request.ika_message_approval = *ika_message_approval;
+    request.message_digest = args.message_digest;
+    request.message_metadata_digest = args.message_metadata_digest;
+    request.destination_chain_id = args.destination_chain_id;
+    request.asset_hash = args.asset_hash;
+    request.recipient_hash = args.recipient_hash;
+    request.amount = args.amount;
+    request.signature_scheme = args.signature_scheme;
     request.created_at = created_at;
     request.bump = bump;
-    Ok(())
+}
+
+fn decide(
+    request: &mut GuardSigningRequest,
+    status: SigningStatus,
+    rejection_reason: RejectionReason,
+    ika_message_approval: &Pubkey,
+) {
+    request.status = status;
+    request.rejection_reason = rejection_reason;
+    request.ika_message_approval = *ika_message_approval;
 }
@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+use crate::error::GuardError;
+use crate::events::Armed;
+use crate::ika_cpi::DWalletAccount;
+use crate::ArmGuardedDwallet;
+
+/// Arm a guard after its dWallet's authority was transferred to this
+/// program's CPI authority. Only the guard's `pre_arm_authority` can arm
+/// it: the CPI authority is shared by every guard, so the transfer alone
+/// does not show whose guard the owner meant to hand the dWallet to.
+/// Approvals are rejected with NotArmed until this succeeds.
+pub fn handler(ctx: Context<ArmGuardedDwallet>) -> Result<()> {
+    let dwallet = DWalletAccount::try_parse(&ctx.accounts.dwallet)?
+        .ok_or(GuardError::InvalidDwallet)?;
+    require!(dwallet.is_active(), GuardError::DwalletNotActive);
+    require_keys_eq!(
+        dwallet.authority,
+        ctx.accounts.cpi_authority.key(),
+        GuardError::DwalletAuthorityNotHeld
+    );
+
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.armed = true;
+
+    emit!(Armed {
+        guarded_dwallet: guarded.key(),
+        dwallet: guarded.dwallet,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Armed GuardedDwallet for dWallet {}", guarded.dwallet);
+
+    Ok(())
+}
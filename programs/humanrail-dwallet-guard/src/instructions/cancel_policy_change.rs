@@ -0,0 +1,10 @@
+use anchor_lang::prelude::*;
+use crate::CancelPolicyChange;
+
+pub fn handler(ctx: Context<CancelPolicyChange>) -> Result<()> {
+    msg!(
+        "Cancelled pending policy change for GuardedDwallet {}",
+        ctx.accounts.guarded_dwallet.key()
+    );
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use crate::error::GuardError;
+use crate::ika_cpi::DWalletAccount;
+use crate::CloseGuardedDwallet;
+
+/// Close a frozen GuardedDwallet and refund its rent to the principal.
+/// Refused while approvals are still awaiting Ika, and while the dWallet's
+/// authority is still this program's CPI authority PDA, since nothing could
+/// approve messages for it afterwards. A dWallet account that cannot be
+/// read is treated as still held.
+///
+/// A guard re-initialized at the same seeds would pick up anything still
+/// keyed to this one, so a pending policy change has to be cancelled and
+/// requests waiting for co-signers closed first.
+pub fn handler(ctx: Context<CloseGuardedDwallet>) -> Result<()> {
+    let guarded = &ctx.accounts.guarded_dwallet;
+    require!(guarded.frozen, GuardError::NotFrozen);
+    require!(guarded.outstanding_approvals == 0, GuardError::OutstandingApprovals);
+    require!(guarded.pending_cosigns == 0, GuardError::PendingCosigns);
+    require!(
+        ctx.accounts.pending_policy_change.data_is_empty(),
+        GuardError::PolicyChangePending
+    );
+
+    let dwallet = DWalletAccount::try_parse(&ctx.accounts.dwallet)?
+        .ok_or(GuardError::DwalletAuthorityStillHeld)?;
+    require!(
+        dwallet.authority != ctx.accounts.cpi_authority.key(),
+        GuardError::DwalletAuthorityStillHeld
+    );
+
+    msg!("Closed GuardedDwallet {}", guarded.key());
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+use crate::error::GuardError;
+use crate::ika_cpi::MessageApprovalAccount;
+use crate::state::{GuardSigningRequest, SigningStatus};
+use crate::CloseSigningRequest;
+
+/// Close a GuardSigningRequest and refund its rent to the original payer.
+/// Rejected requests can be closed at any time; others only after the
+/// retention window. An unconfirmed approval can only be closed by the
+/// principal, once its Ika MessageApproval is signed or gone, and also
+/// needs the GuardedDwallet so its outstanding approval can be released.
+/// Closing a request still waiting for co-signers also needs the
+/// GuardedDwallet, which stops counting it.
+pub fn handler(ctx: Context<CloseSigningRequest>) -> Result<()> {
+    let clock = Clock::get()?;
+    let request = &ctx.accounts.guard_signing_request;
+
+    let is_rejected = request.status == SigningStatus::Rejected;
+    let retention_elapsed = clock.unix_timestamp
+        >= request.created_at.saturating_add(GuardSigningRequest::RETENTION_SECS);
+    require!(is_rejected || retention_elapsed, GuardError::SigningRequestRetained);
+
+    // An approval that was never confirmed as signed still counts against
+    // the guard; release it so release_dwallet is not blocked forever, but
+    // only once Ika can no longer sign it.
+    if request.status == SigningStatus::Approved {
+        let guarded = ctx
+            .accounts
+            .guarded_dwallet
+            .as_mut()
+            .ok_or(GuardError::OutstandingApprovals)?;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            guarded.principal,
+            GuardError::UnauthorizedPrincipal
+        );
+        let message_approval = ctx
+            .accounts
+            .message_approval
+            .as_ref()
+            .ok_or(GuardError::MessageApprovalStillOpen)?;
+        if let Some(approval) = MessageApprovalAccount::try_parse(message_approval)? {
+            require!(approval.is_signed(), GuardError::MessageApprovalStillOpen);
+        }
+        guarded.outstanding_approvals = guarded.outstanding_approvals.saturating_sub(1);
+    } else if request.status == SigningStatus::PendingCosign {
+        ctx.accounts
+            .guarded_dwallet
+            .as_mut()
+            .ok_or(GuardError::PendingCosigns)?
+            .release_pending_cosign();
+    }
+
+    msg!(
+        "Closed signing request {:?} for GuardedDwallet {}",
+        request.request_id,
+        request.guarded_dwallet
+    );
+
+    Ok(())
+}
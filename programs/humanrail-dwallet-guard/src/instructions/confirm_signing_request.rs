@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use crate::error::GuardError;
+use crate::ika_cpi::MessageApprovalAccount;
+use crate::state::SigningStatus;
+use crate::ConfirmSigningRequest;
+
+/// Mark an approved GuardSigningRequest as signed once its Ika
+/// MessageApproval carries a signature. Permissionless: anyone may
+/// confirm, since the outcome is read from the Ika account.
+pub fn handler(ctx: Context<ConfirmSigningRequest>) -> Result<()> {
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    let request = &mut ctx.accounts.guard_signing_request;
+    require!(request.status == SigningStatus::Approved, GuardError::SigningRequestNotApproved);
+
+    let approval = MessageApprovalAccount::try_parse(&ctx.accounts.message_approval)?
+        .ok_or(GuardError::MessageApprovalNotSigned)?;
+    require!(approval.is_signed(), GuardError::MessageApprovalNotSigned);
+
+    request.status = SigningStatus::Signed;
+    guarded.outstanding_approvals = guarded.outstanding_approvals.saturating_sub(1);
+
+    msg!(
+        "Confirmed signing request {:?} as signed",
+        request.request_id
+    );
+
+    Ok(())
+}
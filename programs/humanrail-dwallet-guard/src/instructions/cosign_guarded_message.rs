@@ -0,0 +1,144 @@
+use anchor_lang::prelude::*;
+use crate::error::GuardError;
+use crate::events::Cosigned;
+use crate::state::{GuardedDwallet, RejectionReason, SigningStatus};
+use crate::CosignGuardedMessage;
+use super::approve::{
+    check_policy, check_signing_params, delegation_is_live, emit_approved, emit_rejected,
+    note_rejection,
+};
+
+/// Add a co-signature to a PendingCosign request. When it brings the
+/// request to `cosign_required`, the policy is re-checked against the
+/// current spend counters; the request is then either rejected or approved,
+/// its spend counted and the Ika approve_message CPI sent.
+pub fn handler(ctx: Context<CosignGuardedMessage>) -> Result<()> {
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    let request = &mut ctx.accounts.guard_signing_request;
+    let cosigner = ctx.accounts.cosigner.key();
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(
+        request.status == SigningStatus::PendingCosign,
+        GuardError::SigningRequestNotPending
+    );
+    require!(guarded.is_cosigner(&cosigner), GuardError::UnauthorizedCosigner);
+    require!(
+        !request.cosigned_by.contains(&cosigner),
+        GuardError::AlreadyCosigned
+    );
+    require!(
+        request.cosigned_by.len() < GuardedDwallet::MAX_COSIGNERS,
+        GuardError::CosignerListFull
+    );
+    request.cosigned_by.push(cosigner);
+
+    let approvals = guarded.cosign_count(request);
+    emit!(Cosigned {
+        guarded_dwallet: guarded.key(),
+        request_id: request.request_id,
+        cosigner,
+        approvals: approvals as u8,
+        timestamp: now,
+    });
+
+    if approvals < usize::from(guarded.cosign_required) {
+        msg!(
+            "Co-signed request {:?} ({}/{})",
+            request.request_id,
+            approvals,
+            guarded.cosign_required
+        );
+        return Ok(());
+    }
+
+    guarded.release_pending_cosign();
+
+    // ------------------------------------------------------------------
+    // Threshold reached — the policy may have changed while pending, and
+    // an agent removed in the meantime no longer has a delegation
+    // ------------------------------------------------------------------
+    let agent_signed = request.agent != Pubkey::default();
+    let agent = if agent_signed {
+        guarded.find_agent(&request.agent).copied()
+    } else {
+        None
+    };
+    let delegation_live = agent_signed == agent.is_some()
+        && (guarded.demo_mode
+            || delegation_is_live(
+                guarded,
+                &ctx.accounts.human_profile,
+                ctx.accounts.humanrail_capability.as_deref(),
+                agent.as_ref(),
+                now,
+            ));
+    let signing_rejection =
+        check_signing_params(guarded, request.signature_scheme, &request.user_pubkey);
+    let rejection_reason = if !delegation_live {
+        RejectionReason::CapabilityInactive
+    } else if signing_rejection != RejectionReason::None {
+        signing_rejection
+    } else {
+        check_policy(
+            guarded,
+            agent.as_ref(),
+            now,
+            request.destination_chain_id,
+            &request.asset_hash,
+            &request.recipient_hash,
+            request.amount,
+        )
+    };
+
+    if rejection_reason != RejectionReason::None {
+        request.status = SigningStatus::Rejected;
+        request.rejection_reason = rejection_reason;
+        emit_rejected(request, now);
+        note_rejection(guarded, rejection_reason, now);
+        msg!(
+            "Rejected co-signed request {:?}: {:?}",
+            request.request_id,
+            rejection_reason
+        );
+        return Ok(());
+    }
+
+    guarded.record_approval(
+        agent.as_ref().map(|entry| &entry.agent),
+        request.destination_chain_id,
+        &request.asset_hash,
+        request.amount,
+        now,
+    )?;
+    request.status = SigningStatus::Approved;
+
+    let dwallet_ctx = crate::ika_cpi::DWalletContext {
+        dwallet_program: ctx.accounts.dwallet_program.to_account_info(),
+        cpi_authority: ctx.accounts.cpi_authority.to_account_info(),
+        caller_program: ctx.accounts.program.to_account_info(),
+        cpi_authority_bump: ctx.bumps.cpi_authority,
+    };
+
+    dwallet_ctx.approve_message(
+        &ctx.accounts.coordinator,
+        &ctx.accounts.message_approval,
+        &ctx.accounts.dwallet,
+        &ctx.accounts.cosigner.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        request.message_digest,
+        request.message_metadata_digest,
+        request.user_pubkey,
+        request.signature_scheme,
+        request.message_approval_bump,
+    )?;
+
+    emit_approved(request, now);
+
+    msg!(
+        "Approved co-signed request {:?} and CPI'd Ika approve_message",
+        request.request_id
+    );
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use crate::error::GuardError;
+use crate::events::PolicyChanged;
+use crate::humanrail::verify_agent_link;
+use crate::state::PolicyChange;
+use crate::ExecutePolicyChange;
+
+pub fn handler(ctx: Context<ExecutePolicyChange>) -> Result<()> {
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+    let pending = &ctx.accounts.pending_policy_change;
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+
+    // The current delay is used, so raising it also holds back changes
+    // that are already pending.
+    require!(
+        now >= pending.proposed_at.saturating_add(guarded.policy_change_delay),
+        GuardError::PolicyChangeNotReady
+    );
+
+    // An agent added through the timelock gets the same HumanRail checks as
+    // add_agent, against the accounts the change recorded.
+    if let PolicyChange::AddAgent { entry } = &pending.change {
+        let (Some(agent), Some(capability)) =
+            (&ctx.accounts.agent, &ctx.accounts.humanrail_capability)
+        else {
+            return err!(GuardError::InvalidAgent);
+        };
+        require_keys_eq!(agent.key(), entry.agent, GuardError::InvalidAgent);
+        require_keys_eq!(capability.key(), entry.capability, GuardError::InvalidCapability);
+        verify_agent_link(&guarded.principal, agent, capability, now)?;
+    }
+
+    guarded.apply_policy_change(&pending.change, now)?;
+
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: now,
+    });
+
+    msg!(
+        "Executed policy change for GuardedDwallet {} (policy_nonce={})",
+        guarded.key(),
+        guarded.policy_nonce
+    );
+
+    Ok(())
+}
@@ -1,9 +1,19 @@
 use anchor_lang::prelude::*;
+use crate::events::Frozen;
 use crate::FreezeGuardedDwallet;
 
 pub fn handler(ctx: Context<FreezeGuardedDwallet>) -> Result<()> {
     let guarded = &mut ctx.accounts.guarded_dwallet;
     guarded.frozen = true;
-    msg!("Frozen GuardedDwallet {}", guarded.key());
+    emit!(Frozen {
+        guarded_dwallet: guarded.key(),
+        authority: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    msg!(
+        "Frozen GuardedDwallet {} by {}",
+        guarded.key(),
+        ctx.accounts.authority.key()
+    );
     Ok(())
 }
@@ -1,54 +1,151 @@
 use anchor_lang::prelude::*;
 use crate::error::GuardError;
+use crate::humanrail::{verify_agent_link, HumanProfileRecord};
+use crate::ika_cpi::{curve_schemes, DWalletAccount};
+use crate::state::{
+    AgentEntry, AssetLimits, AssetPolicy, DailyWindow, GuardedDwallet, PeriodCounter, RecipientEntry,
+    TradingWindow, VelocityLimits,
+};
+use crate::events::GuardedDwalletInitialized;
 use crate::InitializeGuardedDwallet;
 
+/// Policy of a new guard: its first chain, asset and recipient, the asset's
+/// limits and the activation window. Shared with the demo initializer.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct InitializeGuardedDwalletArgs {
+    pub allowed_chain_id: u32,
+    pub allowed_asset_hash: [u8; 32],
+    pub allowed_recipient_hash: [u8; 32],
+    pub per_tx_limit: u64,
+    pub daily_limit: u64,
+    pub total_limit: u64,
+    pub expires_at: i64,
+    pub daily_window: DailyWindow,
+    pub not_before: i64,
+    pub min_interval_secs: i64,
+}
+
 pub fn handler(
     ctx: Context<InitializeGuardedDwallet>,
-    allowed_chain_id: u32,
-    allowed_asset_hash: [u8; 32],
-    allowed_recipient_hash: [u8; 32],
-    per_tx_limit: u64,
-    daily_limit: u64,
-    total_limit: u64,
-    expires_at: i64,
+    args: InitializeGuardedDwalletArgs,
 ) -> Result<()> {
+    let InitializeGuardedDwalletArgs {
+        allowed_chain_id,
+        allowed_asset_hash,
+        allowed_recipient_hash,
+        per_tx_limit,
+        daily_limit,
+        total_limit,
+        expires_at,
+        daily_window,
+        not_before,
+        min_interval_secs,
+    } = args;
     let clock = Clock::get()?;
 
-    // 1. Expiry must be in the future
-    require!(expires_at > clock.unix_timestamp, GuardError::InvalidExpiry);
+    // 1. Limits, activation window and cooldown must be sane
+    let limits = AssetLimits {
+        per_tx_limit,
+        daily_limit,
+        total_limit,
+        ..AssetLimits::default()
+    };
+    GuardedDwallet::validate_policy(
+        &limits,
+        expires_at,
+        not_before,
+        min_interval_secs,
+        clock.unix_timestamp,
+    )?;
+
+    // 2. HumanRail links: the principal's own profile, an active agent they
+    //    own, and a live signing capability from the principal to that agent
+    let principal = ctx.accounts.principal.key();
+    let profile = HumanProfileRecord::try_parse(&ctx.accounts.human_profile)?;
+    require_keys_eq!(profile.wallet, principal, GuardError::InvalidHumanProfile);
 
-    // 2. Limits must be sane
-    require!(per_tx_limit > 0, GuardError::InvalidLimitConfig);
-    require!(daily_limit > 0, GuardError::InvalidLimitConfig);
-    require!(per_tx_limit <= daily_limit, GuardError::InvalidLimitConfig);
-    if total_limit > 0 {
-        require!(daily_limit <= total_limit, GuardError::InvalidLimitConfig);
-    }
+    verify_agent_link(
+        &principal,
+        &ctx.accounts.agent,
+        &ctx.accounts.humanrail_capability,
+        clock.unix_timestamp,
+    )?;
+
+    // 3. The dWallet must be a live Ika dWallet the principal controls, so
+    //    nobody can claim the guard for someone else's dWallet; its curve
+    //    decides which signature schemes can be used. A dWallet already
+    //    handed to our CPI authority starts armed; otherwise the guard
+    //    stays unarmed until the principal hands it over and calls arm.
+    let dwallet = DWalletAccount::try_parse(&ctx.accounts.dwallet)?
+        .ok_or(GuardError::InvalidDwallet)?;
+    require!(dwallet.is_active(), GuardError::DwalletNotActive);
+    let armed = dwallet.authority == ctx.accounts.cpi_authority.key();
+    require!(
+        armed || dwallet.authority == principal,
+        GuardError::DwalletNotOwnedByPrincipal
+    );
+    let allowed_schemes = curve_schemes(dwallet.curve).ok_or(GuardError::InvalidDwallet)?;
 
     let guarded = &mut ctx.accounts.guarded_dwallet;
-    guarded.version = 1;
-    guarded.principal = ctx.accounts.principal.key();
+    guarded.version = GuardedDwallet::VERSION;
+    guarded.principal = principal;
     guarded.human_profile = ctx.accounts.human_profile.key();
-    guarded.agent = ctx.accounts.agent.key();
-    guarded.humanrail_capability = ctx.accounts.humanrail_capability.key();
     guarded.dwallet = ctx.accounts.dwallet.key();
-    guarded.allowed_chain_id = allowed_chain_id;
-    guarded.allowed_asset_hash = allowed_asset_hash;
-    guarded.allowed_recipient_hash = allowed_recipient_hash;
-    guarded.per_tx_limit = per_tx_limit;
-    guarded.daily_limit = daily_limit;
-    guarded.total_limit = total_limit;
-    guarded.daily_spent = 0;
-    guarded.total_spent = 0;
-    guarded.last_spend_day = 0;
     guarded.expires_at = expires_at;
     guarded.frozen = false;
     guarded.bump = ctx.bumps.guarded_dwallet;
+    guarded.policy_nonce = 0;
+    guarded.policy_change_delay = 0;
+    guarded.outstanding_approvals = 0;
+    guarded.pending_cosigns = 0;
+    guarded.demo_mode = false;
+    guarded.daily_window = daily_window;
+    guarded.velocity = VelocityLimits::default();
+    guarded.hourly_approvals = PeriodCounter::default();
+    guarded.consecutive_rejections = 0;
+    guarded.trading_window = TradingWindow::ALWAYS;
+    guarded.not_before = not_before;
+    guarded.min_interval_secs = min_interval_secs;
+    guarded.last_approval_at = 0;
+    guarded.cosign_required = 0;
+    guarded.pending_principal = Pubkey::default();
+    guarded.curve = dwallet.curve;
+    guarded.allowed_schemes = allowed_schemes;
+    guarded.principal_digests = false;
+    guarded.armed = armed;
+    guarded.pre_arm_authority = guarded.principal;
+    guarded.release_authority = Pubkey::default();
+    guarded.allowed_chain_ids = vec![allowed_chain_id];
+    guarded.assets = vec![AssetPolicy::new(allowed_chain_id, allowed_asset_hash, limits)];
+    guarded.recipients = vec![RecipientEntry {
+        chain_id: allowed_chain_id,
+        recipient_hash: allowed_recipient_hash,
+        label: [0u8; 32],
+        per_tx_cap: 0,
+    }];
+    guarded.cosigners = Vec::new();
+    guarded.agents = vec![AgentEntry {
+        agent: ctx.accounts.agent.key(),
+        capability: ctx.accounts.humanrail_capability.key(),
+        per_tx_limit: 0,
+        daily_limit: 0,
+    }];
+    guarded.agent_spend = Vec::new();
+    guarded.guardians = Vec::new();
+
+    emit!(GuardedDwalletInitialized {
+        guarded_dwallet: guarded.key(),
+        principal: guarded.principal,
+        agent: ctx.accounts.agent.key(),
+        dwallet: guarded.dwallet,
+        expires_at,
+        timestamp: clock.unix_timestamp,
+    });
 
     msg!(
         "Initialized GuardedDwallet for principal={} agent={} dwallet={}",
         guarded.principal,
-        guarded.agent,
+        ctx.accounts.agent.key,
         guarded.dwallet
     );
 
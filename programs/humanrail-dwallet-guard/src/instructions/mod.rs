@@ -1,5 +1,37 @@
 pub mod initialize;
+#[cfg(feature = "demo")]
 pub mod initialize_demo;
 pub mod freeze;
 pub mod unfreeze;
 pub mod approve;
+pub mod cosign_guarded_message;
+pub mod add_allowed_chain;
+pub mod remove_allowed_chain;
+pub mod add_recipient;
+pub mod remove_recipient;
+pub mod add_asset_policy;
+pub mod remove_asset_policy;
+pub mod update_policy;
+pub mod set_policy_change_delay;
+pub mod set_velocity_limits;
+pub mod set_trading_window;
+pub mod add_cosigner;
+pub mod remove_cosigner;
+pub mod set_cosign_required;
+pub mod add_agent;
+pub mod remove_agent;
+pub mod set_agent_limits;
+pub mod set_allowed_schemes;
+pub mod set_principal_digests;
+pub mod add_guardian;
+pub mod remove_guardian;
+pub mod propose_principal_transfer;
+pub mod accept_principal_transfer;
+pub mod propose_policy_change;
+pub mod execute_policy_change;
+pub mod cancel_policy_change;
+pub mod close_signing_request;
+pub mod close_guarded_dwallet;
+pub mod confirm_signing_request;
+pub mod arm_guarded_dwallet;
+pub mod release_dwallet;
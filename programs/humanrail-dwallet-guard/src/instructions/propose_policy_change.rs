@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use crate::state::PolicyChange;
+use crate::ProposePolicyChange;
+
+pub fn handler(ctx: Context<ProposePolicyChange>, change: PolicyChange) -> Result<()> {
+    let clock = Clock::get()?;
+    let guarded = &ctx.accounts.guarded_dwallet;
+
+    // Dry-run against a copy so a change that can never apply is refused now
+    // rather than after the delay.
+    let mut preview = (**guarded).clone();
+    preview.apply_policy_change(&change, clock.unix_timestamp)?;
+
+    let pending = &mut ctx.accounts.pending_policy_change;
+    pending.guarded_dwallet = guarded.key();
+    pending.change = change;
+    pending.proposed_at = clock.unix_timestamp;
+    pending.policy_nonce = guarded.policy_nonce;
+    pending.bump = ctx.bumps.pending_policy_change;
+
+    msg!(
+        "Proposed policy change for GuardedDwallet {} (executable at {})",
+        guarded.key(),
+        clock.unix_timestamp.saturating_add(guarded.policy_change_delay)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+use crate::events::PrincipalTransferProposed;
+use crate::ProposePrincipalTransfer;
+
+/// Nothing changes until `new_principal` accepts, so a mistyped key can
+/// be replaced or cancelled by proposing again.
+pub fn handler(ctx: Context<ProposePrincipalTransfer>, new_principal: Pubkey) -> Result<()> {
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.pending_principal = new_principal;
+    emit!(PrincipalTransferProposed {
+        guarded_dwallet: guarded.key(),
+        principal: guarded.principal,
+        pending_principal: new_principal,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    msg!(
+        "Proposed principal transfer of GuardedDwallet {} to {}",
+        guarded.key(),
+        new_principal
+    );
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+use crate::error::GuardError;
+use crate::events::{DwalletReleased, Frozen, PolicyChanged};
+use crate::state::PolicyChange;
+use crate::ReleaseDwallet;
+
+/// Freeze the guard and transfer dWallet authority from this program's
+/// CPI authority PDA to `new_authority`. Refused while approved requests
+/// are still waiting on Ika, so a released dWallet cannot sign anything
+/// the guard approved earlier.
+///
+/// Releasing lifts every limit, so it is a loosening ReleaseDwallet policy
+/// change: applied here when the guard has no timelock, otherwise it must
+/// already have been executed for this `new_authority`, and each executed
+/// change covers a single release. The guard is left frozen so nothing is
+/// approved for a dWallet it no longer controls.
+pub fn handler(ctx: Context<ReleaseDwallet>, new_authority: Pubkey) -> Result<()> {
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    require!(guarded.outstanding_approvals == 0, GuardError::OutstandingApprovals);
+    let now = Clock::get()?.unix_timestamp;
+
+    let executed = guarded.release_authority != Pubkey::default()
+        && guarded.release_authority == new_authority;
+    if !executed {
+        guarded.apply_policy_change_now(&PolicyChange::ReleaseDwallet { new_authority }, now)?;
+        emit!(PolicyChanged {
+            guarded_dwallet: guarded.key(),
+            policy_nonce: guarded.policy_nonce,
+            timestamp: now,
+        });
+    }
+
+    guarded.frozen = true;
+    guarded.armed = false;
+    guarded.pre_arm_authority = new_authority;
+
+    let dwallet_ctx = crate::ika_cpi::DWalletContext {
+        dwallet_program: ctx.accounts.dwallet_program.to_account_info(),
+        cpi_authority: ctx.accounts.cpi_authority.to_account_info(),
+        caller_program: ctx.accounts.program.to_account_info(),
+        cpi_authority_bump: ctx.bumps.cpi_authority,
+    };
+    dwallet_ctx.transfer_dwallet(&ctx.accounts.dwallet, &new_authority)?;
+
+    // The executed change is used up; a release after the guard is re-armed
+    // needs a new one.
+    guarded.release_authority = Pubkey::default();
+
+    emit!(Frozen {
+        guarded_dwallet: guarded.key(),
+        authority: ctx.accounts.principal.key(),
+        timestamp: now,
+    });
+    emit!(DwalletReleased {
+        guarded_dwallet: guarded.key(),
+        dwallet: guarded.dwallet,
+        new_authority,
+        timestamp: now,
+    });
+
+    msg!(
+        "Released dWallet {} to {}",
+        guarded.dwallet,
+        new_authority
+    );
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+use crate::events::PolicyChanged;
+use crate::RemoveAgent;
+
+/// Pending co-sign requests from the removed agent are rejected once
+/// their threshold is reached.
+pub fn handler(ctx: Context<RemoveAgent>, agent: Pubkey) -> Result<()> {
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.remove_agent(&agent)?;
+    guarded.bump_policy_nonce();
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    msg!(
+        "Removed agent {} from GuardedDwallet {} ({} agents)",
+        agent,
+        guarded.key(),
+        guarded.agents.len()
+    );
+    Ok(())
+}
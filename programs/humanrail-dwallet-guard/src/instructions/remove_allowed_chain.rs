@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+use crate::events::PolicyChanged;
+use crate::RemoveAllowedChain;
+
+pub fn handler(ctx: Context<RemoveAllowedChain>, chain_id: u32) -> Result<()> {
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.remove_chain(chain_id)?;
+    guarded.bump_policy_nonce();
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    msg!(
+        "Removed chain {} and its assets/recipients from GuardedDwallet {}",
+        chain_id,
+        guarded.key()
+    );
+    Ok(())
+}
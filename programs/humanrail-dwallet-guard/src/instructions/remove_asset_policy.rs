@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+use crate::events::PolicyChanged;
+use crate::RemoveAssetPolicy;
+
+pub fn handler(
+    ctx: Context<RemoveAssetPolicy>,
+    chain_id: u32,
+    asset_hash: [u8; 32],
+) -> Result<()> {
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.remove_asset(chain_id, &asset_hash)?;
+    guarded.bump_policy_nonce();
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    msg!(
+        "Removed asset policy from GuardedDwallet {} ({} assets)",
+        guarded.key(),
+        guarded.assets.len()
+    );
+    Ok(())
+}
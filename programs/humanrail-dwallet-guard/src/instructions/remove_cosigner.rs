@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+use crate::events::PolicyChanged;
+use crate::RemoveCosigner;
+
+/// Co-signatures the removed key already gave on pending requests stop
+/// counting towards their threshold.
+pub fn handler(ctx: Context<RemoveCosigner>, cosigner: Pubkey) -> Result<()> {
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.remove_cosigner(&cosigner)?;
+    guarded.bump_policy_nonce();
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    msg!(
+        "Removed co-signer {} from GuardedDwallet {} ({} co-signers)",
+        cosigner,
+        guarded.key(),
+        guarded.cosigners.len()
+    );
+    Ok(())
+}
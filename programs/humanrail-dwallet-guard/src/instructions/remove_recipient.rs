@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+use crate::events::PolicyChanged;
+use crate::RemoveRecipient;
+
+pub fn handler(
+    ctx: Context<RemoveRecipient>,
+    chain_id: u32,
+    recipient_hash: [u8; 32],
+) -> Result<()> {
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.remove_recipient(chain_id, &recipient_hash)?;
+    guarded.bump_policy_nonce();
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    msg!(
+        "Removed recipient from GuardedDwallet {} ({} allowlisted)",
+        guarded.key(),
+        guarded.recipients.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+use crate::state::PolicyChange;
+use crate::events::PolicyChanged;
+use crate::SetAgentLimits;
+
+/// Lowering a sub-limit applies immediately; raising or clearing one is a
+/// loosening change and has to go through propose_policy_change when a
+/// timelock is configured.
+pub fn handler(
+    ctx: Context<SetAgentLimits>,
+    agent: Pubkey,
+    per_tx_limit: u64,
+    daily_limit: u64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.apply_policy_change_now(
+        &PolicyChange::SetAgentLimits {
+            agent,
+            per_tx_limit,
+            daily_limit,
+        },
+        clock.unix_timestamp,
+    )?;
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: clock.unix_timestamp,
+    });
+    msg!(
+        "Set agent {} limits on GuardedDwallet {}: per_tx={} daily={}",
+        agent,
+        guarded.key(),
+        per_tx_limit,
+        daily_limit
+    );
+    Ok(())
+}
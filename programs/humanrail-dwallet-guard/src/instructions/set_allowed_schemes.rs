@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+use crate::state::PolicyChange;
+use crate::events::PolicyChanged;
+use crate::SetAllowedSchemes;
+
+/// Dropping schemes applies immediately; allowing new ones has to go
+/// through propose_policy_change when a timelock is configured.
+pub fn handler(ctx: Context<SetAllowedSchemes>, schemes: u8) -> Result<()> {
+    let clock = Clock::get()?;
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.apply_policy_change_now(
+        &PolicyChange::SetAllowedSchemes { schemes },
+        clock.unix_timestamp,
+    )?;
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: clock.unix_timestamp,
+    });
+    msg!(
+        "Set allowed signature schemes on GuardedDwallet {}: {:#04x}",
+        guarded.key(),
+        schemes
+    );
+    Ok(())
+}
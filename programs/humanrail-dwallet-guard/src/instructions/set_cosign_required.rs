@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+use crate::state::PolicyChange;
+use crate::events::PolicyChanged;
+use crate::SetCosignRequired;
+
+/// Raising a non-zero count applies immediately. Lowering it, or setting
+/// the first one (which makes above-threshold requests approvable at all),
+/// is a loosening change and has to go through propose_policy_change when a
+/// timelock is configured.
+pub fn handler(ctx: Context<SetCosignRequired>, required: u8) -> Result<()> {
+    let clock = Clock::get()?;
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.apply_policy_change_now(
+        &PolicyChange::SetCosignRequired { required },
+        clock.unix_timestamp,
+    )?;
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: clock.unix_timestamp,
+    });
+    msg!(
+        "GuardedDwallet {} now needs {} of {} co-signers",
+        guarded.key(),
+        required,
+        guarded.cosigners.len()
+    );
+    Ok(())
+}
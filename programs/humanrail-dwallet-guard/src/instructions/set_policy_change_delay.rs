@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+use crate::state::PolicyChange;
+use crate::events::PolicyChanged;
+use crate::SetPolicyChangeDelay;
+
+/// Raising the delay applies immediately; lowering it is itself a
+/// loosening change and has to go through propose_policy_change.
+pub fn handler(ctx: Context<SetPolicyChangeDelay>, delay_secs: i64) -> Result<()> {
+    let clock = Clock::get()?;
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.apply_policy_change_now(
+        &PolicyChange::SetPolicyChangeDelay { delay_secs },
+        clock.unix_timestamp,
+    )?;
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: clock.unix_timestamp,
+    });
+    msg!(
+        "Set policy change delay on GuardedDwallet {} to {}s",
+        guarded.key(),
+        delay_secs
+    );
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+use crate::state::PolicyChange;
+use crate::events::PolicyChanged;
+use crate::SetPrincipalDigests;
+
+/// Turning principal digests off applies immediately; turning them on has
+/// to go through propose_policy_change when a timelock is configured.
+pub fn handler(ctx: Context<SetPrincipalDigests>, allowed: bool) -> Result<()> {
+    let clock = Clock::get()?;
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.apply_policy_change_now(
+        &PolicyChange::SetPrincipalDigests { allowed },
+        clock.unix_timestamp,
+    )?;
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: clock.unix_timestamp,
+    });
+    msg!(
+        "Set principal digests on GuardedDwallet {}: {}",
+        guarded.key(),
+        allowed
+    );
+    Ok(())
+}
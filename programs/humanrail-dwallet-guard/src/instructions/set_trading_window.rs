@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+use crate::state::{PolicyChange, TradingWindow};
+use crate::events::PolicyChanged;
+use crate::SetTradingWindow;
+
+/// Removing hours or weekdays applies immediately; allowing new ones or
+/// moving the UTC offset has to go through propose_policy_change when a
+/// timelock is configured.
+pub fn handler(ctx: Context<SetTradingWindow>, window: TradingWindow) -> Result<()> {
+    let clock = Clock::get()?;
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.apply_policy_change_now(
+        &PolicyChange::SetTradingWindow { window },
+        clock.unix_timestamp,
+    )?;
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: clock.unix_timestamp,
+    });
+    msg!(
+        "Set trading window on GuardedDwallet {}: hours={:#08x} weekdays={:#04x} offset={}s",
+        guarded.key(),
+        window.allowed_hours,
+        window.allowed_weekdays,
+        window.utc_offset_secs
+    );
+    Ok(())
+}
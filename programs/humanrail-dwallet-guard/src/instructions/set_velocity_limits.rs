@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use crate::state::{PolicyChange, VelocityLimits};
+use crate::events::PolicyChanged;
+use crate::SetVelocityLimits;
+
+/// Lowering a limit applies immediately; raising or disabling one is a
+/// loosening change and has to go through propose_policy_change when a
+/// timelock is configured.
+pub fn handler(ctx: Context<SetVelocityLimits>, limits: VelocityLimits) -> Result<()> {
+    let clock = Clock::get()?;
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.apply_policy_change_now(
+        &PolicyChange::SetVelocityLimits { limits },
+        clock.unix_timestamp,
+    )?;
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: clock.unix_timestamp,
+    });
+    msg!(
+        "Set velocity limits on GuardedDwallet {}: {} approvals/hour, freeze after {} rejections",
+        guarded.key(),
+        limits.max_approvals_per_hour,
+        limits.max_consecutive_rejections
+    );
+    Ok(())
+}
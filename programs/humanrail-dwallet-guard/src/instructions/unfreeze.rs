@@ -1,9 +1,15 @@
 use anchor_lang::prelude::*;
+use crate::events::Unfrozen;
 use crate::UnfreezeGuardedDwallet;
 
 pub fn handler(ctx: Context<UnfreezeGuardedDwallet>) -> Result<()> {
     let guarded = &mut ctx.accounts.guarded_dwallet;
     guarded.frozen = false;
+    guarded.consecutive_rejections = 0;
+    emit!(Unfrozen {
+        guarded_dwallet: guarded.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
     msg!("Unfrozen GuardedDwallet {}", guarded.key());
     Ok(())
 }
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+use crate::state::{AssetLimits, PolicyChange};
+use crate::events::PolicyChanged;
+use crate::UpdateGuardedDwalletPolicy;
+
+/// Replace the limits of one asset entry and the policy expiry.
+/// Spend counters are preserved; `policy_nonce` is bumped. Loosening
+/// updates are refused while a policy change timelock is configured.
+pub fn handler(
+    ctx: Context<UpdateGuardedDwalletPolicy>,
+    chain_id: u32,
+    asset_hash: [u8; 32],
+    limits: AssetLimits,
+    expires_at: i64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+
+    let guarded = &mut ctx.accounts.guarded_dwallet;
+    guarded.apply_policy_change_now(
+        &PolicyChange::UpdatePolicy {
+            chain_id,
+            asset_hash,
+            limits,
+            expires_at,
+        },
+        clock.unix_timestamp,
+    )?;
+
+    emit!(PolicyChanged {
+        guarded_dwallet: guarded.key(),
+        policy_nonce: guarded.policy_nonce,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "Updated policy for GuardedDwallet {} (policy_nonce={})",
+        guarded.key(),
+        guarded.policy_nonce
+    );
+
+    Ok(())
+}
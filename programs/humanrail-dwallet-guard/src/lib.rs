@@ -1,8 +1,12 @@
 use anchor_lang::prelude::*;
 
 pub mod error;
+pub mod events;
+pub mod humanrail;
 pub mod ika_cpi;
 pub mod instructions;
+pub mod payload;
+pub mod policy;
 pub mod state;
 
 // HumanRail program IDs (used in constraints)
@@ -22,15 +26,6 @@ declare_id!("Bzxgvxp9rZt2qeY7UNnvic9jHQdVFMw7mWzXvjuwLnT2");
 // ------------------------------------------------------------------
 
 #[derive(Accounts)]
-#[instruction(
-    allowed_chain_id: u32,
-    allowed_asset_hash: [u8; 32],
-    allowed_recipient_hash: [u8; 32],
-    per_tx_limit: u64,
-    daily_limit: u64,
-    total_limit: u64,
-    expires_at: i64,
-)]
 pub struct InitializeGuardedDwallet<'info> {
     #[account(mut)]
     pub principal: Signer<'info>,
@@ -41,127 +36,750 @@ pub struct InitializeGuardedDwallet<'info> {
         space = 8 + state::GuardedDwallet::LEN,
         seeds = [
             b"guarded_dwallet",
-            principal.key().as_ref(),
-            agent.key.as_ref(),
             dwallet.key.as_ref(),
         ],
         bump,
     )]
     pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
 
-    /// CHECK: Human Registry account; owner verified below
+    /// CHECK: Human Registry HumanProfile; contents verified in handler
     #[account(owner = HUMANRAIL_HUMAN_REGISTRY_PROGRAM_ID @ error::GuardError::InvalidHumanProfile)]
-    pub human_profile: AccountInfo<'info>,
+    pub human_profile: UncheckedAccount<'info>,
 
-    /// CHECK: Agent Registry account; owner verified below
+    /// CHECK: Agent Registry AgentProfile; contents verified in handler
     #[account(owner = HUMANRAIL_AGENT_REGISTRY_PROGRAM_ID @ error::GuardError::InvalidAgent)]
-    pub agent: AccountInfo<'info>,
+    pub agent: UncheckedAccount<'info>,
 
-    /// CHECK: Delegation capability account; owner verified below
+    /// CHECK: Delegation Capability; contents verified in handler
     #[account(owner = HUMANRAIL_DELEGATION_PROGRAM_ID @ error::GuardError::InvalidCapability)]
-    pub humanrail_capability: AccountInfo<'info>,
+    pub humanrail_capability: UncheckedAccount<'info>,
 
-    /// CHECK: Ika dWallet pubkey — stored as reference only
-    pub dwallet: AccountInfo<'info>,
+    /// CHECK: Ika dWallet account; owner, state and authority checked in handler
+    pub dwallet: UncheckedAccount<'info>,
+
+    /// CHECK: CPI authority PDA (derived from __ika_cpi_authority seed)
+    #[account(
+        seeds = [ika_cpi::CPI_AUTHORITY_SEED],
+        bump,
+    )]
+    pub cpi_authority: UncheckedAccount<'info>,
 
     pub system_program: Program<'info, System>,
 }
 
-/// Devnet-only account struct that skips HumanRail owner checks.
-/// Allows testing GuardedDwallet lifecycle without requiring
-/// canRegisterAgents=true on the human profile.
+/// Devnet-only account struct that skips HumanRail owner checks.
+/// Allows testing GuardedDwallet lifecycle without requiring
+/// canRegisterAgents=true on the human profile.
+#[cfg(feature = "demo")]
+#[derive(Accounts)]
+pub struct InitializeGuardedDwalletDemo<'info> {
+    #[account(mut)]
+    pub principal: Signer<'info>,
+
+    #[account(
+        init,
+        payer = principal,
+        space = 8 + state::GuardedDwallet::LEN,
+        seeds = [
+            b"guarded_dwallet",
+            dwallet.key.as_ref(),
+        ],
+        bump,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+
+    /// CHECK: No owner check — demo mode allows any pubkey
+    pub human_profile: UncheckedAccount<'info>,
+
+    /// CHECK: No owner check — demo mode allows any pubkey
+    pub agent: UncheckedAccount<'info>,
+
+    /// CHECK: No owner check — demo mode allows any pubkey
+    pub humanrail_capability: UncheckedAccount<'info>,
+
+    /// CHECK: Ika dWallet account; owner, state and authority checked in handler
+    pub dwallet: UncheckedAccount<'info>,
+
+    /// CHECK: CPI authority PDA (derived from __ika_cpi_authority seed)
+    #[account(
+        seeds = [ika_cpi::CPI_AUTHORITY_SEED],
+        bump,
+    )]
+    pub cpi_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FreezeGuardedDwallet<'info> {
+    /// The principal or a guardian
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.can_freeze(&authority.key()) @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct UnfreezeGuardedDwallet<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGuardedDwalletPolicy<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct AddAllowedChain<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAllowedChain<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct AddRecipient<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveRecipient<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct AddAssetPolicy<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAssetPolicy<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct SetPolicyChangeDelay<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct SetVelocityLimits<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct SetTradingWindow<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct AddCosigner<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveCosigner<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct SetCosignRequired<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct AddAgent<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+
+    /// CHECK: Agent Registry AgentProfile; contents verified in handler
+    #[account(owner = HUMANRAIL_AGENT_REGISTRY_PROGRAM_ID @ error::GuardError::InvalidAgent)]
+    pub agent: UncheckedAccount<'info>,
+
+    /// CHECK: Delegation Capability; contents verified in handler
+    #[account(owner = HUMANRAIL_DELEGATION_PROGRAM_ID @ error::GuardError::InvalidCapability)]
+    pub humanrail_capability: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAgent<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct SetAgentLimits<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct SetAllowedSchemes<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrincipalDigests<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct AddGuardian<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveGuardian<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct ProposePrincipalTransfer<'info> {
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptPrincipalTransfer<'info> {
+    /// Must match guarded_dwallet.pending_principal; checked in handler
+    pub new_principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+
+    /// CHECK: Human Registry HumanProfile of the new principal; verified in
+    /// handler unless the guard is in demo mode
+    pub human_profile: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposePolicyChange<'info> {
+    #[account(mut)]
+    pub principal: Signer<'info>,
+
+    #[account(
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+
+    #[account(
+        init,
+        payer = principal,
+        space = 8 + state::PendingPolicyChange::LEN,
+        seeds = [
+            b"pending_policy_change",
+            guarded_dwallet.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub pending_policy_change: Account<'info, state::PendingPolicyChange>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecutePolicyChange<'info> {
+    #[account(mut)]
+    pub principal: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+
+    #[account(
+        mut,
+        close = principal,
+        seeds = [
+            b"pending_policy_change",
+            guarded_dwallet.key().as_ref(),
+        ],
+        bump = pending_policy_change.bump,
+        has_one = guarded_dwallet,
+    )]
+    pub pending_policy_change: Account<'info, state::PendingPolicyChange>,
+
+    /// CHECK: Agent Registry AgentProfile of an AddAgent change; required
+    /// for that change and verified in handler
+    pub agent: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Delegation Capability of an AddAgent change; required for
+    /// that change and verified in handler
+    pub humanrail_capability: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPolicyChange<'info> {
+    #[account(mut)]
+    pub principal: Signer<'info>,
+
+    #[account(
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+        constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+
+    #[account(
+        mut,
+        close = principal,
+        seeds = [
+            b"pending_policy_change",
+            guarded_dwallet.key().as_ref(),
+        ],
+        bump = pending_policy_change.bump,
+        has_one = guarded_dwallet,
+    )]
+    pub pending_policy_change: Account<'info, state::PendingPolicyChange>,
+}
+
 #[derive(Accounts)]
-#[instruction(
-    allowed_chain_id: u32,
-    allowed_asset_hash: [u8; 32],
-    allowed_recipient_hash: [u8; 32],
-    per_tx_limit: u64,
-    daily_limit: u64,
-    total_limit: u64,
-    expires_at: i64,
-)]
-pub struct InitializeGuardedDwalletDemo<'info> {
-    #[account(mut)]
-    pub principal: Signer<'info>,
+pub struct CloseSigningRequest<'info> {
+    /// Either the principal or the account that paid for the request
+    #[account(
+        constraint = authority.key() == guard_signing_request.principal
+            || authority.key() == guard_signing_request.payer
+            @ error::GuardError::UnauthorizedPrincipal,
+    )]
+    pub authority: Signer<'info>,
 
     #[account(
-        init,
-        payer = principal,
-        space = 8 + state::GuardedDwallet::LEN,
+        mut,
+        close = payer,
+        seeds = [
+            b"guard_signing_request",
+            guard_signing_request.guarded_dwallet.as_ref(),
+            &guard_signing_request.request_id,
+        ],
+        bump = guard_signing_request.bump,
+    )]
+    pub guard_signing_request: Account<'info, state::GuardSigningRequest>,
+
+    /// CHECK: Rent refund destination; must be the original payer
+    #[account(mut, address = guard_signing_request.payer)]
+    pub payer: UncheckedAccount<'info>,
+
+    /// Required only when closing an approved request that was never
+    /// confirmed as signed, so its outstanding approval can be released,
+    /// or a request still waiting for co-signers.
+    #[account(
+        mut,
+        address = guard_signing_request.guarded_dwallet,
+    )]
+    pub guarded_dwallet: Option<Account<'info, state::GuardedDwallet>>,
+
+    /// CHECK: Ika MessageApproval recorded on the request; required with
+    /// guarded_dwallet and parsed in handler
+    #[account(address = guard_signing_request.ika_message_approval)]
+    pub message_approval: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmSigningRequest<'info> {
+    #[account(
+        mut,
         seeds = [
             b"guarded_dwallet",
-            principal.key().as_ref(),
-            agent.key.as_ref(),
-            dwallet.key.as_ref(),
+            guarded_dwallet.dwallet.as_ref(),
         ],
-        bump,
+        bump = guarded_dwallet.bump,
     )]
     pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
 
-    /// CHECK: No owner check — demo mode allows any pubkey
-    pub human_profile: AccountInfo<'info>,
+    #[account(
+        mut,
+        has_one = guarded_dwallet,
+        seeds = [
+            b"guard_signing_request",
+            guarded_dwallet.key().as_ref(),
+            &guard_signing_request.request_id,
+        ],
+        bump = guard_signing_request.bump,
+    )]
+    pub guard_signing_request: Account<'info, state::GuardSigningRequest>,
 
-    /// CHECK: No owner check — demo mode allows any pubkey
-    pub agent: AccountInfo<'info>,
+    /// CHECK: Ika MessageApproval recorded on the request; parsed in handler
+    #[account(address = guard_signing_request.ika_message_approval)]
+    pub message_approval: UncheckedAccount<'info>,
+}
 
-    /// CHECK: No owner check — demo mode allows any pubkey
-    pub humanrail_capability: AccountInfo<'info>,
+#[derive(Accounts)]
+pub struct ArmGuardedDwallet<'info> {
+    /// The key that handed the dWallet to the CPI authority
+    #[account(
+        constraint = authority.key() == guarded_dwallet.pre_arm_authority
+            @ error::GuardError::UnauthorizedArmAuthority,
+    )]
+    pub authority: Signer<'info>,
 
-    /// CHECK: Ika dWallet pubkey — stored as reference only
-    pub dwallet: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
 
-    pub system_program: Program<'info, System>,
+    /// CHECK: Ika dWallet account; owner, state and authority checked in handler
+    #[account(address = guarded_dwallet.dwallet @ error::GuardError::DwalletMismatch)]
+    pub dwallet: UncheckedAccount<'info>,
+
+    /// CHECK: CPI authority PDA (derived from __ika_cpi_authority seed)
+    #[account(
+        seeds = [ika_cpi::CPI_AUTHORITY_SEED],
+        bump,
+    )]
+    pub cpi_authority: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
-pub struct FreezeGuardedDwallet<'info> {
+pub struct ReleaseDwallet<'info> {
     pub principal: Signer<'info>,
 
     #[account(
         mut,
         seeds = [
             b"guarded_dwallet",
-            guarded_dwallet.principal.as_ref(),
-            guarded_dwallet.agent.as_ref(),
             guarded_dwallet.dwallet.as_ref(),
         ],
         bump = guarded_dwallet.bump,
         constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
     )]
     pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+
+    /// CHECK: Ika dWallet account whose authority is transferred
+    #[account(mut, address = guarded_dwallet.dwallet @ error::GuardError::DwalletMismatch)]
+    pub dwallet: UncheckedAccount<'info>,
+
+    /// CHECK: CPI authority PDA (derived from __ika_cpi_authority seed)
+    #[account(
+        seeds = [ika_cpi::CPI_AUTHORITY_SEED],
+        bump,
+    )]
+    pub cpi_authority: UncheckedAccount<'info>,
+
+    /// CHECK: This program's executable account (required by Ika for caller verification)
+    #[account(address = crate::ID)]
+    pub program: UncheckedAccount<'info>,
+
+    /// CHECK: Ika dWallet program
+    #[account(address = ika_cpi::IKA_PROGRAM_ID)]
+    pub dwallet_program: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
-pub struct UnfreezeGuardedDwallet<'info> {
+pub struct CloseGuardedDwallet<'info> {
+    #[account(mut)]
     pub principal: Signer<'info>,
 
     #[account(
         mut,
+        close = principal,
         seeds = [
             b"guarded_dwallet",
-            guarded_dwallet.principal.as_ref(),
-            guarded_dwallet.agent.as_ref(),
             guarded_dwallet.dwallet.as_ref(),
         ],
         bump = guarded_dwallet.bump,
         constraint = guarded_dwallet.principal == principal.key() @ error::GuardError::UnauthorizedPrincipal,
     )]
     pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+
+    /// CHECK: Ika dWallet account; authority inspected in handler
+    #[account(address = guarded_dwallet.dwallet @ error::GuardError::DwalletMismatch)]
+    pub dwallet: UncheckedAccount<'info>,
+
+    /// CHECK: CPI authority PDA (derived from __ika_cpi_authority seed)
+    #[account(
+        seeds = [ika_cpi::CPI_AUTHORITY_SEED],
+        bump,
+    )]
+    pub cpi_authority: UncheckedAccount<'info>,
+
+    /// CHECK: PendingPolicyChange PDA of this guard; must not exist, checked
+    /// in handler
+    #[account(
+        seeds = [
+            b"pending_policy_change",
+            guarded_dwallet.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub pending_policy_change: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(
-    request_id: [u8; 32],
-    message_digest: [u8; 32],
-    message_metadata_digest: [u8; 32],
-    destination_chain_id: u32,
-    asset_hash: [u8; 32],
-    recipient_hash: [u8; 32],
-    amount: u64,
-    user_pubkey: [u8; 32],
-    signature_scheme: u16,
-    message_approval_bump: u8,
-)]
+#[instruction(args: instructions::approve::ApproveGuardedMessageArgs)]
 pub struct ApproveGuardedMessage<'info> {
     #[account(mut)]
     pub requester: Signer<'info>,
@@ -170,8 +788,6 @@ pub struct ApproveGuardedMessage<'info> {
         mut,
         seeds = [
             b"guarded_dwallet",
-            guarded_dwallet.principal.as_ref(),
-            guarded_dwallet.agent.as_ref(),
             guarded_dwallet.dwallet.as_ref(),
         ],
         bump = guarded_dwallet.bump,
@@ -185,39 +801,114 @@ pub struct ApproveGuardedMessage<'info> {
         seeds = [
             b"guard_signing_request",
             guarded_dwallet.key().as_ref(),
-            &request_id,
+            &args.request_id,
         ],
         bump,
     )]
     pub guard_signing_request: Account<'info, state::GuardSigningRequest>,
 
     /// CHECK: Verified against GuardedDwallet.dwallet in handler
-    pub dwallet: AccountInfo<'info>,
+    pub dwallet: UncheckedAccount<'info>,
 
-    /// CHECK: Optional Agent Registry account for agent signer verification
-    pub agent_registry_account: Option<AccountInfo<'info>>,
+    /// CHECK: Agent Registry AgentProfile of one of guarded_dwallet.agents;
+    /// required when an agent signs. Key and contents are checked in handler.
+    pub agent_registry_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: HumanProfile recorded on the guard; re-read in handler
+    #[account(address = guarded_dwallet.human_profile @ error::GuardError::InvalidHumanProfile)]
+    pub human_profile: UncheckedAccount<'info>,
+
+    /// CHECK: Delegation Capability recorded for the signing agent; required
+    /// when an agent signs. Key and contents are checked in handler.
+    pub humanrail_capability: Option<UncheckedAccount<'info>>,
 
     /// CHECK: CPI authority PDA (derived from __ika_cpi_authority seed)
     #[account(
         seeds = [ika_cpi::CPI_AUTHORITY_SEED],
         bump,
     )]
-    pub cpi_authority: AccountInfo<'info>,
+    pub cpi_authority: UncheckedAccount<'info>,
 
     /// CHECK: This program's executable account (required by Ika for caller verification)
     #[account(address = crate::ID)]
-    pub program: AccountInfo<'info>,
+    pub program: UncheckedAccount<'info>,
 
     /// CHECK: Ika dWallet program
     #[account(address = ika_cpi::IKA_PROGRAM_ID)]
-    pub dwallet_program: AccountInfo<'info>,
+    pub dwallet_program: UncheckedAccount<'info>,
 
     /// CHECK: Ika coordinator account (DWalletCoordinator PDA)
-    pub coordinator: AccountInfo<'info>,
+    pub coordinator: UncheckedAccount<'info>,
 
     /// CHECK: Message approval PDA — created by Ika inside CPI
     #[account(mut)]
-    pub message_approval: AccountInfo<'info>,
+    pub message_approval: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CosignGuardedMessage<'info> {
+    /// Configured co-signer; pays for the MessageApproval when this
+    /// co-signature completes the request
+    #[account(mut)]
+    pub cosigner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"guarded_dwallet",
+            guarded_dwallet.dwallet.as_ref(),
+        ],
+        bump = guarded_dwallet.bump,
+    )]
+    pub guarded_dwallet: Account<'info, state::GuardedDwallet>,
+
+    #[account(
+        mut,
+        has_one = guarded_dwallet,
+        seeds = [
+            b"guard_signing_request",
+            guarded_dwallet.key().as_ref(),
+            &guard_signing_request.request_id,
+        ],
+        bump = guard_signing_request.bump,
+    )]
+    pub guard_signing_request: Account<'info, state::GuardSigningRequest>,
+
+    /// CHECK: Ika dWallet account of the guard
+    #[account(address = guarded_dwallet.dwallet @ error::GuardError::DwalletMismatch)]
+    pub dwallet: UncheckedAccount<'info>,
+
+    /// CHECK: HumanProfile recorded on the guard; re-read in handler
+    #[account(address = guarded_dwallet.human_profile @ error::GuardError::InvalidHumanProfile)]
+    pub human_profile: UncheckedAccount<'info>,
+
+    /// CHECK: Delegation Capability recorded for the request's agent;
+    /// required for agent requests. Key and contents are checked in handler.
+    pub humanrail_capability: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: CPI authority PDA (derived from __ika_cpi_authority seed)
+    #[account(
+        seeds = [ika_cpi::CPI_AUTHORITY_SEED],
+        bump,
+    )]
+    pub cpi_authority: UncheckedAccount<'info>,
+
+    /// CHECK: This program's executable account (required by Ika for caller verification)
+    #[account(address = crate::ID)]
+    pub program: UncheckedAccount<'info>,
+
+    /// CHECK: Ika dWallet program
+    #[account(address = ika_cpi::IKA_PROGRAM_ID)]
+    pub dwallet_program: UncheckedAccount<'info>,
+
+    /// CHECK: Ika coordinator account (DWalletCoordinator PDA)
+    pub coordinator: UncheckedAccount<'info>,
+
+    /// CHECK: Message approval PDA recorded on the request — created by Ika inside CPI
+    #[account(mut, address = guard_signing_request.ika_message_approval)]
+    pub message_approval: UncheckedAccount<'info>,
 
     pub system_program: Program<'info, System>,
 }
@@ -228,32 +919,20 @@ pub mod humanrail_dwallet_guard {
 
     pub fn initialize_guarded_dwallet(
         ctx: Context<InitializeGuardedDwallet>,
-        allowed_chain_id: u32,
-        allowed_asset_hash: [u8; 32],
-        allowed_recipient_hash: [u8; 32],
-        per_tx_limit: u64,
-        daily_limit: u64,
-        total_limit: u64,
-        expires_at: i64,
+        args: instructions::initialize::InitializeGuardedDwalletArgs,
     ) -> Result<()> {
-        instructions::initialize::handler(ctx, allowed_chain_id, allowed_asset_hash,
-            allowed_recipient_hash, per_tx_limit, daily_limit, total_limit, expires_at)
+        instructions::initialize::handler(ctx, args)
     }
 
     /// Devnet-only initializer that skips HumanRail owner checks.
     /// Use this for testing when real HumanRail attestations are unavailable.
+    /// Only built with the `demo` feature.
+    #[cfg(feature = "demo")]
     pub fn initialize_guarded_dwallet_demo(
         ctx: Context<InitializeGuardedDwalletDemo>,
-        allowed_chain_id: u32,
-        allowed_asset_hash: [u8; 32],
-        allowed_recipient_hash: [u8; 32],
-        per_tx_limit: u64,
-        daily_limit: u64,
-        total_limit: u64,
-        expires_at: i64,
+        args: instructions::initialize::InitializeGuardedDwalletArgs,
     ) -> Result<()> {
-        instructions::initialize_demo::handler(ctx, allowed_chain_id, allowed_asset_hash,
-            allowed_recipient_hash, per_tx_limit, daily_limit, total_limit, expires_at)
+        instructions::initialize_demo::handler(ctx, args)
     }
 
     pub fn freeze_guarded_dwallet(
@@ -268,21 +947,265 @@ pub mod humanrail_dwallet_guard {
         instructions::unfreeze::handler(ctx)
     }
 
-    pub fn approve_guarded_message(
-        ctx: Context<ApproveGuardedMessage>,
-        request_id: [u8; 32],
-        message_digest: [u8; 32],
-        message_metadata_digest: [u8; 32],
-        destination_chain_id: u32,
+    /// Principal-only update of one asset's limits and the policy expiry.
+    /// Runs the same validation as `initialize_guarded_dwallet`.
+    pub fn update_guarded_dwallet_policy(
+        ctx: Context<UpdateGuardedDwalletPolicy>,
+        chain_id: u32,
         asset_hash: [u8; 32],
+        limits: state::AssetLimits,
+        expires_at: i64,
+    ) -> Result<()> {
+        instructions::update_policy::handler(ctx, chain_id, asset_hash, limits, expires_at)
+    }
+
+    pub fn add_allowed_chain(
+        ctx: Context<AddAllowedChain>,
+        chain_id: u32,
+    ) -> Result<()> {
+        instructions::add_allowed_chain::handler(ctx, chain_id)
+    }
+
+    /// Removes the chain along with every asset policy and recipient scoped to it.
+    pub fn remove_allowed_chain(
+        ctx: Context<RemoveAllowedChain>,
+        chain_id: u32,
+    ) -> Result<()> {
+        instructions::remove_allowed_chain::handler(ctx, chain_id)
+    }
+
+    pub fn add_recipient(
+        ctx: Context<AddRecipient>,
+        chain_id: u32,
+        recipient_hash: [u8; 32],
+        label: [u8; 32],
+        per_tx_cap: u64,
+    ) -> Result<()> {
+        instructions::add_recipient::handler(ctx, chain_id, recipient_hash, label, per_tx_cap)
+    }
+
+    pub fn remove_recipient(
+        ctx: Context<RemoveRecipient>,
+        chain_id: u32,
         recipient_hash: [u8; 32],
-        amount: u64,
-        user_pubkey: [u8; 32],
-        signature_scheme: u16,
-        message_approval_bump: u8,
-    ) -> Result<()> {
-        instructions::approve::handler(ctx, request_id, message_digest,
-            message_metadata_digest, destination_chain_id, asset_hash, recipient_hash,
-            amount, user_pubkey, signature_scheme, message_approval_bump)
+    ) -> Result<()> {
+        instructions::remove_recipient::handler(ctx, chain_id, recipient_hash)
+    }
+
+    pub fn add_asset_policy(
+        ctx: Context<AddAssetPolicy>,
+        chain_id: u32,
+        asset_hash: [u8; 32],
+        limits: state::AssetLimits,
+    ) -> Result<()> {
+        instructions::add_asset_policy::handler(ctx, chain_id, asset_hash, limits)
+    }
+
+    pub fn remove_asset_policy(
+        ctx: Context<RemoveAssetPolicy>,
+        chain_id: u32,
+        asset_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::remove_asset_policy::handler(ctx, chain_id, asset_hash)
+    }
+
+    /// Raise the loosening timelock immediately, or lower it via the timelock.
+    pub fn set_policy_change_delay(
+        ctx: Context<SetPolicyChangeDelay>,
+        delay_secs: i64,
+    ) -> Result<()> {
+        instructions::set_policy_change_delay::handler(ctx, delay_secs)
+    }
+
+    /// Tighten velocity limits immediately; loosening goes through the timelock.
+    pub fn set_velocity_limits(
+        ctx: Context<SetVelocityLimits>,
+        limits: state::VelocityLimits,
+    ) -> Result<()> {
+        instructions::set_velocity_limits::handler(ctx, limits)
+    }
+
+    /// Narrow the trading window immediately; widening goes through the timelock.
+    pub fn set_trading_window(
+        ctx: Context<SetTradingWindow>,
+        window: state::TradingWindow,
+    ) -> Result<()> {
+        instructions::set_trading_window::handler(ctx, window)
+    }
+
+    /// Add a co-signer. Loosening, so it goes through the timelock when set.
+    pub fn add_cosigner(
+        ctx: Context<AddCosigner>,
+        cosigner: Pubkey,
+    ) -> Result<()> {
+        instructions::add_cosigner::handler(ctx, cosigner)
+    }
+
+    pub fn remove_cosigner(
+        ctx: Context<RemoveCosigner>,
+        cosigner: Pubkey,
+    ) -> Result<()> {
+        instructions::remove_cosigner::handler(ctx, cosigner)
+    }
+
+    /// Set how many co-signers must approve requests above a co-sign threshold.
+    pub fn set_cosign_required(
+        ctx: Context<SetCosignRequired>,
+        required: u8,
+    ) -> Result<()> {
+        instructions::set_cosign_required::handler(ctx, required)
+    }
+
+    /// Authorize another agent on the guard. Loosening, so it goes through
+    /// the timelock when set.
+    pub fn add_agent(
+        ctx: Context<AddAgent>,
+        per_tx_limit: u64,
+        daily_limit: u64,
+    ) -> Result<()> {
+        instructions::add_agent::handler(ctx, per_tx_limit, daily_limit)
+    }
+
+    pub fn remove_agent(
+        ctx: Context<RemoveAgent>,
+        agent: Pubkey,
+    ) -> Result<()> {
+        instructions::remove_agent::handler(ctx, agent)
+    }
+
+    /// Set an agent's per-tx and daily sub-limits (0 = no sub-limit).
+    pub fn set_agent_limits(
+        ctx: Context<SetAgentLimits>,
+        agent: Pubkey,
+        per_tx_limit: u64,
+        daily_limit: u64,
+    ) -> Result<()> {
+        instructions::set_agent_limits::handler(ctx, agent, per_tx_limit, daily_limit)
+    }
+
+    /// Set the signature schemes requests may use (bit n = scheme n),
+    /// within those the dWallet's curve supports.
+    pub fn set_allowed_schemes(
+        ctx: Context<SetAllowedSchemes>,
+        schemes: u8,
+    ) -> Result<()> {
+        instructions::set_allowed_schemes::handler(ctx, schemes)
+    }
+
+    /// Allow or stop approvals of bare digests requested by the principal.
+    pub fn set_principal_digests(
+        ctx: Context<SetPrincipalDigests>,
+        allowed: bool,
+    ) -> Result<()> {
+        instructions::set_principal_digests::handler(ctx, allowed)
+    }
+
+    /// Let `guardian` freeze the guard. Guardians cannot unfreeze it or
+    /// change policy.
+    pub fn add_guardian(
+        ctx: Context<AddGuardian>,
+        guardian: Pubkey,
+    ) -> Result<()> {
+        instructions::add_guardian::handler(ctx, guardian)
+    }
+
+    pub fn remove_guardian(
+        ctx: Context<RemoveGuardian>,
+        guardian: Pubkey,
+    ) -> Result<()> {
+        instructions::remove_guardian::handler(ctx, guardian)
+    }
+
+    /// First step of handing the principal role to `new_principal`, who
+    /// completes it with accept_principal_transfer. Proposing the default
+    /// pubkey cancels a pending transfer.
+    pub fn propose_principal_transfer(
+        ctx: Context<ProposePrincipalTransfer>,
+        new_principal: Pubkey,
+    ) -> Result<()> {
+        instructions::propose_principal_transfer::handler(ctx, new_principal)
+    }
+
+    pub fn accept_principal_transfer(
+        ctx: Context<AcceptPrincipalTransfer>,
+    ) -> Result<()> {
+        instructions::accept_principal_transfer::handler(ctx)
+    }
+
+    /// Stage a loosening change; it can be executed once
+    /// `proposed_at + policy_change_delay` has passed.
+    pub fn propose_policy_change(
+        ctx: Context<ProposePolicyChange>,
+        change: state::PolicyChange,
+    ) -> Result<()> {
+        instructions::propose_policy_change::handler(ctx, change)
+    }
+
+    /// Apply a staged change once its delay has passed. AddAgent changes
+    /// need the agent and capability accounts, checked as in add_agent.
+    pub fn execute_policy_change(
+        ctx: Context<ExecutePolicyChange>,
+    ) -> Result<()> {
+        instructions::execute_policy_change::handler(ctx)
+    }
+
+    pub fn cancel_policy_change(
+        ctx: Context<CancelPolicyChange>,
+    ) -> Result<()> {
+        instructions::cancel_policy_change::handler(ctx)
+    }
+
+    pub fn approve_guarded_message(
+        ctx: Context<ApproveGuardedMessage>,
+        args: instructions::approve::ApproveGuardedMessageArgs,
+        payload: payload::SigningPayload,
+    ) -> Result<()> {
+        instructions::approve::handler(ctx, args, payload)
+    }
+
+    /// Co-sign a PendingCosign request; the last required co-signature
+    /// sends it to Ika.
+    pub fn cosign_guarded_message(
+        ctx: Context<CosignGuardedMessage>,
+    ) -> Result<()> {
+        instructions::cosign_guarded_message::handler(ctx)
+    }
+
+    /// Close a rejected request, or any request past its retention window,
+    /// refunding rent to whoever paid for it.
+    pub fn close_signing_request(
+        ctx: Context<CloseSigningRequest>,
+    ) -> Result<()> {
+        instructions::close_signing_request::handler(ctx)
+    }
+
+    /// Close a frozen guard once the dWallet authority has been handed back.
+    pub fn close_guarded_dwallet(
+        ctx: Context<CloseGuardedDwallet>,
+    ) -> Result<()> {
+        instructions::close_guarded_dwallet::handler(ctx)
+    }
+
+    /// Mark an approved request as signed once Ika has produced the signature.
+    pub fn confirm_signing_request(
+        ctx: Context<ConfirmSigningRequest>,
+    ) -> Result<()> {
+        instructions::confirm_signing_request::handler(ctx)
+    }
+
+    /// Arm a guard once its dWallet authority has been handed to our CPI
+    /// authority; signed by the key that handed it over.
+    pub fn arm_guarded_dwallet(ctx: Context<ArmGuardedDwallet>) -> Result<()> {
+        instructions::arm_guarded_dwallet::handler(ctx)
+    }
+
+    /// Freeze the guard and hand dWallet authority to `new_authority`. Under
+    /// a timelock the release must first go through propose / execute as a
+    /// ReleaseDwallet policy change.
+    pub fn release_dwallet(
+        ctx: Context<ReleaseDwallet>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        instructions::release_dwallet::handler(ctx, new_authority)
     }
 }
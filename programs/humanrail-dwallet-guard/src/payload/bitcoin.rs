@@ -0,0 +1,461 @@
+//! Bitcoin segwit transaction decoding.
+//!
+//! Accepts an unsigned transaction in the legacy serialization (no witness
+//! data, every scriptSig empty) and signs one of its inputs:
+//!
+//! - a P2WPKH input is signed over its BIP-143 sighash preimage with
+//!   ECDSA over double SHA-256, SIGHASH_ALL. BIP-143 commits only to the
+//!   signed input's amount, so the other prevout amounts, and with them
+//!   the fee, could be understated; P2WPKH spends must have one input;
+//! - a P2TR key-path input is signed over its BIP-341 TapSighash preimage
+//!   (epoch byte followed by SigMsg) with BIP-340 Schnorr,
+//!   SIGHASH_DEFAULT, no annex.
+//!
+//! Outputs paying the signed input's own scriptPubKey are change. Every
+//! other output must pay one and the same scriptPubKey, the recipient,
+//! whose hash is keccak256 of the raw script; the asset hash is
+//! keccak256("BTC"). The counted amount is what leaves the wallet: the
+//! recipient outputs plus the fee. Transactions carry no chain id, so the
+//! declared Bitcoin one is used, and each signed input counts the whole
+//! spend again.
+
+use anchor_lang::prelude::*;
+use solana_keccak_hasher as keccak;
+use solana_sha256_hasher as sha256;
+use super::{Decoded, Transfer};
+use crate::ika_cpi::{SCHEME_ECDSA_DOUBLE_SHA256, SCHEME_TAPROOT_SHA256};
+
+/// An output spent by the transaction.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Prevout {
+    pub amount: u64,
+    pub script_pubkey: Vec<u8>,
+}
+
+/// Asset name hashed for bitcoin.
+const NATIVE_ASSET: &[u8] = b"BTC";
+
+const SIGHASH_ALL: u32 = 1;
+const SIGHASH_DEFAULT: u8 = 0;
+const TAPSIGHASH_EPOCH: u8 = 0;
+
+const P2WPKH_LEN: usize = 22;
+const P2TR_LEN: usize = 34;
+
+/// Decodes `tx` for signing input `input_index`, or returns `None` if it
+/// is not a supported segwit spend.
+pub fn decode(
+    tx: &[u8],
+    input_index: u32,
+    prevouts: &[Prevout],
+    chain_id: u32,
+) -> Option<Decoded> {
+    let parsed = Tx::parse(tx)?;
+    if parsed.inputs.len() != prevouts.len() {
+        return None;
+    }
+    let index = usize::try_from(input_index).ok()?;
+    let spent = prevouts.get(index)?;
+    let own_script = spent.script_pubkey.as_slice();
+
+    // What leaves the wallet: everything but change, plus the fee
+    let mut recipient: Option<&[u8]> = None;
+    let mut sent = 0u64;
+    let mut total_out = 0u64;
+    for output in &parsed.outputs {
+        total_out = total_out.checked_add(output.value)?;
+        if output.script_pubkey == own_script {
+            continue;
+        }
+        match recipient {
+            Some(script) if script != output.script_pubkey => return None,
+            _ => recipient = Some(output.script_pubkey),
+        }
+        sent = sent.checked_add(output.value)?;
+    }
+    let total_in = prevouts
+        .iter()
+        .try_fold(0u64, |acc, prevout| acc.checked_add(prevout.amount))?;
+    let fee = total_in.checked_sub(total_out)?;
+
+    let (message, signature_scheme) = if is_p2wpkh(own_script) {
+        if parsed.inputs.len() != 1 {
+            return None;
+        }
+        (
+            bip143_message(&parsed, index, &own_script[2..], spent.amount),
+            SCHEME_ECDSA_DOUBLE_SHA256,
+        )
+    } else if is_p2tr(own_script) {
+        (
+            bip341_message(&parsed, input_index, prevouts),
+            SCHEME_TAPROOT_SHA256,
+        )
+    } else {
+        return None;
+    };
+
+    Some(Decoded {
+        transfer: Transfer {
+            chain_id,
+            asset_hash: keccak::hash(NATIVE_ASSET).to_bytes(),
+            recipient_hash: keccak::hash(recipient?).to_bytes(),
+            amount: sent.checked_add(fee)?,
+        },
+        message_digest: keccak::hash(&message).to_bytes(),
+        signature_scheme,
+    })
+}
+
+fn is_p2wpkh(script: &[u8]) -> bool {
+    script.len() == P2WPKH_LEN && script[0] == 0x00 && script[1] == 0x14
+}
+
+fn is_p2tr(script: &[u8]) -> bool {
+    script.len() == P2TR_LEN && script[0] == 0x51 && script[1] == 0x20
+}
+
+fn sha256d(parts: &[&[u8]]) -> [u8; 32] {
+    sha256::hash(&sha256::hashv(parts).to_bytes()).to_bytes()
+}
+
+/// BIP-143 preimage for SIGHASH_ALL.
+fn bip143_message(tx: &Tx, index: usize, pubkey_hash: &[u8], amount: u64) -> Vec<u8> {
+    let outpoints: Vec<&[u8]> = tx.inputs.iter().map(|input| input.outpoint).collect();
+    let sequences: Vec<&[u8]> = tx.inputs.iter().map(|input| input.sequence).collect();
+    let input = &tx.inputs[index];
+
+    let mut message = Vec::with_capacity(4 + 32 + 32 + 36 + 26 + 8 + 4 + 32 + 4 + 4);
+    message.extend_from_slice(tx.version);
+    message.extend_from_slice(&sha256d(&outpoints));
+    message.extend_from_slice(&sha256d(&sequences));
+    message.extend_from_slice(input.outpoint);
+    // scriptCode of P2WPKH: OP_DUP OP_HASH160 <20> OP_EQUALVERIFY OP_CHECKSIG
+    message.extend_from_slice(&[0x19, 0x76, 0xa9, 0x14]);
+    message.extend_from_slice(pubkey_hash);
+    message.extend_from_slice(&[0x88, 0xac]);
+    message.extend_from_slice(&amount.to_le_bytes());
+    message.extend_from_slice(input.sequence);
+    message.extend_from_slice(&sha256d(&[tx.outputs_raw]));
+    message.extend_from_slice(tx.lock_time);
+    message.extend_from_slice(&SIGHASH_ALL.to_le_bytes());
+    message
+}
+
+/// BIP-341 key-path preimage for SIGHASH_DEFAULT: the epoch byte followed
+/// by SigMsg.
+fn bip341_message(tx: &Tx, input_index: u32, prevouts: &[Prevout]) -> Vec<u8> {
+    let outpoints: Vec<&[u8]> = tx.inputs.iter().map(|input| input.outpoint).collect();
+    let sequences: Vec<&[u8]> = tx.inputs.iter().map(|input| input.sequence).collect();
+    let amounts: Vec<[u8; 8]> = prevouts.iter().map(|p| p.amount.to_le_bytes()).collect();
+    let amounts: Vec<&[u8]> = amounts.iter().map(|a| a.as_slice()).collect();
+    let mut scripts = Vec::new();
+    for prevout in prevouts {
+        write_compact_size(&mut scripts, prevout.script_pubkey.len());
+        scripts.extend_from_slice(&prevout.script_pubkey);
+    }
+
+    let mut message = Vec::with_capacity(1 + 1 + 4 + 4 + 5 * 32 + 1 + 4);
+    message.push(TAPSIGHASH_EPOCH);
+    message.push(SIGHASH_DEFAULT);
+    message.extend_from_slice(tx.version);
+    message.extend_from_slice(tx.lock_time);
+    message.extend_from_slice(&sha256::hashv(&outpoints).to_bytes());
+    message.extend_from_slice(&sha256::hashv(&amounts).to_bytes());
+    message.extend_from_slice(&sha256::hash(&scripts).to_bytes());
+    message.extend_from_slice(&sha256::hashv(&sequences).to_bytes());
+    message.extend_from_slice(&sha256::hash(tx.outputs_raw).to_bytes());
+    message.push(0); // spend_type: key path, no annex
+    message.extend_from_slice(&input_index.to_le_bytes());
+    message
+}
+
+fn write_compact_size(out: &mut Vec<u8>, len: usize) {
+    match len {
+        0..=0xfc => out.push(len as u8),
+        0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(len as u16).to_le_bytes());
+        }
+        _ => {
+            out.push(0xfe);
+            out.extend_from_slice(&(len as u32).to_le_bytes());
+        }
+    }
+}
+
+// ------------------------------------------------------------------
+// Transaction parsing. Fields the sighashes commit to are kept as the
+// raw slices they were serialized as.
+// ------------------------------------------------------------------
+
+struct Tx<'a> {
+    version: &'a [u8],
+    inputs: Vec<Input<'a>>,
+    outputs: Vec<Output<'a>>,
+    /// All outputs as serialized, for hashOutputs / sha_outputs
+    outputs_raw: &'a [u8],
+    lock_time: &'a [u8],
+}
+
+struct Input<'a> {
+    outpoint: &'a [u8],
+    sequence: &'a [u8],
+}
+
+struct Output<'a> {
+    value: u64,
+    script_pubkey: &'a [u8],
+}
+
+impl<'a> Tx<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        let mut reader = Reader(data);
+        let version = reader.take(4)?;
+
+        // A zero input count would be the segwit marker
+        let input_count = reader.compact_size()?;
+        if input_count == 0 {
+            return None;
+        }
+        let mut inputs = Vec::new();
+        for _ in 0..input_count {
+            let outpoint = reader.take(36)?;
+            if reader.compact_size()? != 0 {
+                return None;
+            }
+            let sequence = reader.take(4)?;
+            inputs.push(Input { outpoint, sequence });
+        }
+
+        let outputs_start = reader.0;
+        let output_count = reader.compact_size()?;
+        let counted_len = outputs_start.len() - reader.0.len();
+        let mut outputs = Vec::new();
+        for _ in 0..output_count {
+            let value = u64::from_le_bytes(reader.take(8)?.try_into().ok()?);
+            let script_len = reader.compact_size()?;
+            let script_pubkey = reader.take(script_len)?;
+            outputs.push(Output {
+                value,
+                script_pubkey,
+            });
+        }
+        if outputs.is_empty() {
+            return None;
+        }
+        let outputs_raw = &outputs_start[counted_len..outputs_start.len() - reader.0.len()];
+
+        let lock_time = reader.take(4)?;
+        if !reader.0.is_empty() {
+            return None;
+        }
+        Some(Self {
+            version,
+            inputs,
+            outputs,
+            outputs_raw,
+            lock_time,
+        })
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(head)
+    }
+
+    /// A minimally encoded CompactSize.
+    fn compact_size(&mut self) -> Option<usize> {
+        let (value, min) = match self.take(1)?[0] {
+            0xfd => (u64::from(u16::from_le_bytes(self.take(2)?.try_into().ok()?)), 0xfd),
+            0xfe => (u64::from(u32::from_le_bytes(self.take(4)?.try_into().ok()?)), 0x1_0000),
+            0xff => (u64::from_le_bytes(self.take(8)?.try_into().ok()?), 0x1_0000_0000),
+            byte => return Some(usize::from(byte)),
+        };
+        if value < min {
+            return None;
+        }
+        usize::try_from(value).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::hex;
+
+    /// BIP-143 "Native P2WPKH": input 0 spends P2PK, input 1 P2WPKH.
+    const BIP143_TX: &str = "0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4\
+        ad969f0000000000eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a01\
+        00000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093\
+        510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac11000000";
+    const BIP143_P2PK: &str =
+        "2103c9f4836b9a4f77fc0d81f7bcb01b7f1b35916864b9476c241ce9fc198bd25432ac";
+    const BIP143_P2WPKH: &str = "00141d0f172a0ecb48aee1be1f2687d2963ae33f71a1";
+    const BIP143_SIGHASH: &str =
+        "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670";
+
+    /// Bitcoin Core key-path test case, SIGHASH_DEFAULT.
+    const BIP341_TX: &str = "020000000164eb050a5e3da0c2a65e4786f26d753b7bc69691fabccafb11f7acef36\
+        641f1846010000003101b2b404392a22000000000017a9147f2bde86fe78bf68a0544a4f290e12f0b7e0a08c87\
+        580200000000000017a91425d11723074ecfb96a0a83c3956bfaf362ae0c908758020000000000001600147e20\
+        f938993641de67bb0cdd71682aa34c4d29ad5802000000000000160014c64984dc8761acfa99418bd6bedc79b9\
+        287d652d72000000";
+    const BIP341_PREVOUT_SCRIPT: &str =
+        "542156b39dab4f8f3508e0432cfb41fab110170acaa2d4c42539cb90a4dc7c093bc500";
+    const BIP341_SIGHASH: &str =
+        "33ca0ebfb4a945eeee9569fc0f5040221275f88690b7f8592ada88ce3bdf6703";
+
+    const CHAIN_ID: u32 = crate::payload::BITCOIN_TESTNET_CHAIN_ID;
+
+    fn p2wpkh(fill: u8) -> Vec<u8> {
+        [&[0x00, 0x14][..], &[fill; 20]].concat()
+    }
+
+    fn p2tr(fill: u8) -> Vec<u8> {
+        [&[0x51, 0x20][..], &[fill; 32]].concat()
+    }
+
+    /// Unsigned version 2 transaction spending `inputs` outpoints.
+    fn unsigned_tx(inputs: u8, outputs: &[(u64, &[u8])]) -> Vec<u8> {
+        let mut tx = vec![2, 0, 0, 0, inputs];
+        for i in 0..inputs {
+            tx.extend_from_slice(&[i + 1; 32]);
+            tx.extend_from_slice(&[0, 0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+        }
+        tx.push(outputs.len() as u8);
+        for (value, script) in outputs {
+            tx.extend_from_slice(&value.to_le_bytes());
+            tx.push(script.len() as u8);
+            tx.extend_from_slice(script);
+        }
+        tx.extend_from_slice(&[0, 0, 0, 0]);
+        tx
+    }
+
+    fn prevout(amount: u64, script_pubkey: &[u8]) -> Prevout {
+        Prevout {
+            amount,
+            script_pubkey: script_pubkey.to_vec(),
+        }
+    }
+
+    #[test]
+    fn bip143_preimage_matches_test_vector() {
+        let tx = hex(BIP143_TX);
+        let parsed = Tx::parse(&tx).unwrap();
+        let pubkey_hash = &hex(BIP143_P2WPKH)[2..];
+        let message = bip143_message(&parsed, 1, pubkey_hash, 600_000_000);
+        assert_eq!(sha256d(&[&message]).to_vec(), hex(BIP143_SIGHASH));
+    }
+
+    #[test]
+    fn bip341_preimage_matches_test_vector() {
+        let tx = hex(BIP341_TX);
+        let parsed = Tx::parse(&tx).unwrap();
+        let prevouts = [prevout(2_381_622, &hex(BIP341_PREVOUT_SCRIPT))];
+        let message = bip341_message(&parsed, 0, &prevouts);
+        let tag = sha256::hash(b"TapSighash").to_bytes();
+        let sighash = sha256::hashv(&[&tag, &tag, &message]).to_bytes();
+        assert_eq!(sighash.to_vec(), hex(BIP341_SIGHASH));
+    }
+
+    #[test]
+    fn decodes_p2wpkh_spend() {
+        let own = p2wpkh(0x33);
+        let recipient = p2wpkh(0x22);
+        let tx = unsigned_tx(1, &[(50_000, &recipient), (40_000, &own)]);
+        let decoded = decode(&tx, 0, &[prevout(100_000, &own)], CHAIN_ID).unwrap();
+
+        let message = bip143_message(&Tx::parse(&tx).unwrap(), 0, &own[2..], 100_000);
+        assert_eq!(
+            decoded.transfer,
+            Transfer {
+                chain_id: CHAIN_ID,
+                asset_hash: keccak::hash(b"BTC").to_bytes(),
+                recipient_hash: keccak::hash(&recipient).to_bytes(),
+                // 50_000 sent plus a 10_000 fee; the change stays
+                amount: 60_000,
+            }
+        );
+        assert_eq!(decoded.message_digest, keccak::hash(&message).to_bytes());
+        assert_eq!(decoded.signature_scheme, SCHEME_ECDSA_DOUBLE_SHA256);
+    }
+
+    #[test]
+    fn decodes_p2tr_key_path_spend() {
+        let own = p2tr(0x44);
+        let recipient = p2tr(0x55);
+        let other = p2tr(0x66);
+        let tx = unsigned_tx(2, &[(70_000, &recipient), (25_000, &own)]);
+        let prevouts = [prevout(60_000, &other), prevout(40_000, &own)];
+        let decoded = decode(&tx, 1, &prevouts, CHAIN_ID).unwrap();
+
+        let message = bip341_message(&Tx::parse(&tx).unwrap(), 1, &prevouts);
+        assert_eq!(decoded.transfer.amount, 75_000);
+        assert_eq!(
+            decoded.transfer.recipient_hash,
+            keccak::hash(&recipient).to_bytes()
+        );
+        assert_eq!(decoded.message_digest, keccak::hash(&message).to_bytes());
+        assert_eq!(decoded.signature_scheme, SCHEME_TAPROOT_SHA256);
+    }
+
+    #[test]
+    fn rejects_multi_input_p2wpkh() {
+        let tx = hex(BIP143_TX);
+        let prevouts = [
+            prevout(625_000_000, &hex(BIP143_P2PK)),
+            prevout(600_000_000, &hex(BIP143_P2WPKH)),
+        ];
+        assert!(decode(&tx, 1, &prevouts, CHAIN_ID).is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_transactions() {
+        let own = p2wpkh(0x33);
+        let recipient = p2wpkh(0x22);
+        let tx = unsigned_tx(1, &[(50_000, &recipient)]);
+        let prevouts = [prevout(100_000, &own)];
+        assert!(decode(&tx, 0, &prevouts, CHAIN_ID).is_some());
+
+        assert!(decode(&[], 0, &prevouts, CHAIN_ID).is_none());
+        assert!(decode(&tx[..tx.len() - 1], 0, &prevouts, CHAIN_ID).is_none());
+        assert!(decode(&[tx.as_slice(), &[0]].concat(), 0, &prevouts, CHAIN_ID).is_none());
+        // Prevouts that do not match the inputs, or an input out of range
+        assert!(decode(&tx, 0, &[], CHAIN_ID).is_none());
+        assert!(decode(&tx, 1, &prevouts, CHAIN_ID).is_none());
+        // Outputs worth more than the inputs
+        assert!(decode(&tx, 0, &[prevout(40_000, &own)], CHAIN_ID).is_none());
+        // Segwit marker and flag
+        let with_marker = [&tx[..4], &[0x00, 0x01], &tx[4..]].concat();
+        assert!(decode(&with_marker, 0, &prevouts, CHAIN_ID).is_none());
+        // A non-empty scriptSig
+        let mut script_sig = tx.clone();
+        script_sig[4 + 1 + 36] = 1;
+        script_sig.insert(4 + 1 + 37, 0x51);
+        assert!(decode(&script_sig, 0, &prevouts, CHAIN_ID).is_none());
+        // Two different recipients
+        let split = unsigned_tx(1, &[(30_000, &recipient), (20_000, &p2wpkh(0x77))]);
+        assert!(decode(&split, 0, &prevouts, CHAIN_ID).is_none());
+    }
+
+    #[test]
+    fn rejects_non_minimal_compact_size() {
+        let own = p2wpkh(0x33);
+        let recipient = p2wpkh(0x22);
+        let tx = unsigned_tx(1, &[(50_000, &recipient)]);
+        let prevouts = [prevout(100_000, &own)];
+        // One input, counted with a 0xfd prefix
+        let padded = [&tx[..4], &[0xfd, 0x01, 0x00], &tx[5..]].concat();
+        assert!(decode(&padded, 0, &prevouts, CHAIN_ID).is_none());
+    }
+}
@@ -0,0 +1,283 @@
+//! EIP-1559 transaction decoding.
+//!
+//! Accepts an unsigned type-2 transaction,
+//! `0x02 || rlp([chain_id, nonce, max_priority_fee_per_gas, max_fee_per_gas,
+//! gas_limit, to, value, data, access_list])`, that either sends native
+//! value with empty calldata or calls ERC-20 `transfer(address,uint256)`
+//! with zero value. The message Ika signs is the transaction itself,
+//! under ECDSA over keccak256.
+//!
+//! Hashes follow the off-chain policy tooling: the recipient hash is the
+//! keccak256 of the lowercase `0x`-prefixed address, the asset hash is the
+//! same over the token contract address, or keccak256("NATIVE") for native
+//! value.
+
+use solana_keccak_hasher as keccak;
+use super::{Decoded, Transfer};
+use crate::ika_cpi::SCHEME_ECDSA_KECCAK256;
+
+/// Asset name hashed for a chain's native currency.
+const NATIVE_ASSET: &[u8] = b"NATIVE";
+
+const EIP1559_TX_TYPE: u8 = 0x02;
+
+/// `transfer(address,uint256)` selector.
+const ERC20_TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+const ERC20_TRANSFER_LEN: usize = 4 + 32 + 32;
+
+/// Decodes `tx`, or returns `None` if it is not a supported, canonically
+/// encoded transfer.
+pub fn decode(tx: &[u8]) -> Option<Decoded> {
+    let (&tx_type, body) = tx.split_first()?;
+    if tx_type != EIP1559_TX_TYPE {
+        return None;
+    }
+    let (Item::List(fields), rest) = next_item(body)? else {
+        return None;
+    };
+    if !rest.is_empty() {
+        return None;
+    }
+
+    let mut fields = Fields(fields);
+    let chain_id = u32::try_from(to_u64(fields.uint()?)?).ok()?;
+    fields.uint()?; // nonce
+    fields.uint()?; // max_priority_fee_per_gas
+    fields.uint()?; // max_fee_per_gas
+    fields.uint()?; // gas_limit
+    let to: [u8; 20] = fields.bytes()?.try_into().ok()?;
+    let value = fields.uint()?;
+    let data = fields.bytes()?;
+    fields.list()?; // access_list
+    if !fields.is_empty() {
+        return None;
+    }
+
+    let (asset_hash, recipient, amount) = if data.is_empty() {
+        (keccak::hash(NATIVE_ASSET).to_bytes(), to, to_u64(value)?)
+    } else {
+        if !value.is_empty() {
+            return None;
+        }
+        let (recipient, amount) = decode_erc20_transfer(data)?;
+        (address_hash(&to), recipient, amount)
+    };
+
+    Some(Decoded {
+        transfer: Transfer {
+            chain_id,
+            asset_hash,
+            recipient_hash: address_hash(&recipient),
+            amount,
+        },
+        message_digest: keccak::hash(tx).to_bytes(),
+        signature_scheme: SCHEME_ECDSA_KECCAK256,
+    })
+}
+
+/// Recipient and amount of `transfer(address,uint256)` calldata.
+fn decode_erc20_transfer(data: &[u8]) -> Option<([u8; 20], u64)> {
+    if data.len() != ERC20_TRANSFER_LEN || data[..4] != ERC20_TRANSFER_SELECTOR {
+        return None;
+    }
+    let (address_word, amount_word) = data[4..].split_at(32);
+    if address_word[..12].iter().any(|&b| b != 0) || amount_word[..24].iter().any(|&b| b != 0) {
+        return None;
+    }
+    let recipient = address_word[12..].try_into().ok()?;
+    let amount = u64::from_be_bytes(amount_word[24..].try_into().ok()?);
+    Some((recipient, amount))
+}
+
+/// keccak256 of the lowercase `0x`-prefixed hex address.
+fn address_hash(address: &[u8; 20]) -> [u8; 32] {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut text = [0u8; 42];
+    text[..2].copy_from_slice(b"0x");
+    for (i, byte) in address.iter().enumerate() {
+        text[2 + 2 * i] = HEX[usize::from(byte >> 4)];
+        text[3 + 2 * i] = HEX[usize::from(byte & 0x0f)];
+    }
+    keccak::hash(&text).to_bytes()
+}
+
+/// A big-endian unsigned integer that fits in a u64.
+fn to_u64(bytes: &[u8]) -> Option<u64> {
+    if bytes.len() > 8 {
+        return None;
+    }
+    Some(bytes.iter().fold(0u64, |acc, &b| (acc << 8) | u64::from(b)))
+}
+
+// ------------------------------------------------------------------
+// Minimal RLP decoding. Only canonical encodings are accepted so a
+// transaction has exactly one byte representation, and so one digest.
+// ------------------------------------------------------------------
+
+enum Item<'a> {
+    Bytes(&'a [u8]),
+    /// The encoded items inside a list
+    List(&'a [u8]),
+}
+
+/// Decodes the item at the start of `data`, returning it and the rest.
+fn next_item(data: &[u8]) -> Option<(Item<'_>, &[u8])> {
+    let (&prefix, rest) = data.split_first()?;
+    match prefix {
+        0x00..=0x7f => Some((Item::Bytes(&data[..1]), rest)),
+        0x80..=0xb7 => {
+            let (payload, rest) = split(rest, usize::from(prefix - 0x80))?;
+            // A single byte below 0x80 is its own encoding
+            if payload.len() == 1 && payload[0] < 0x80 {
+                return None;
+            }
+            Some((Item::Bytes(payload), rest))
+        }
+        0xb8..=0xbf => {
+            let (len, rest) = long_len(rest, usize::from(prefix - 0xb7))?;
+            let (payload, rest) = split(rest, len)?;
+            Some((Item::Bytes(payload), rest))
+        }
+        0xc0..=0xf7 => {
+            let (payload, rest) = split(rest, usize::from(prefix - 0xc0))?;
+            Some((Item::List(payload), rest))
+        }
+        0xf8..=0xff => {
+            let (len, rest) = long_len(rest, usize::from(prefix - 0xf7))?;
+            let (payload, rest) = split(rest, len)?;
+            Some((Item::List(payload), rest))
+        }
+    }
+}
+
+fn split(data: &[u8], len: usize) -> Option<(&[u8], &[u8])> {
+    (data.len() >= len).then(|| data.split_at(len))
+}
+
+/// Length of a long string or list, which must be minimally encoded and
+/// too long for the short form.
+fn long_len(data: &[u8], len_of_len: usize) -> Option<(usize, &[u8])> {
+    let (bytes, rest) = split(data, len_of_len)?;
+    if bytes.len() > 4 || bytes[0] == 0 {
+        return None;
+    }
+    let len = bytes.iter().fold(0usize, |acc, &b| (acc << 8) | usize::from(b));
+    (len >= 56).then_some((len, rest))
+}
+
+/// Cursor over the items of a list.
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn next(&mut self) -> Option<Item<'a>> {
+        let (item, rest) = next_item(self.0)?;
+        self.0 = rest;
+        Some(item)
+    }
+
+    fn bytes(&mut self) -> Option<&'a [u8]> {
+        match self.next()? {
+            Item::Bytes(bytes) => Some(bytes),
+            Item::List(_) => None,
+        }
+    }
+
+    fn list(&mut self) -> Option<&'a [u8]> {
+        match self.next()? {
+            Item::List(items) => Some(items),
+            Item::Bytes(_) => None,
+        }
+    }
+
+    /// A uint256 without leading zero bytes; zero is the empty string.
+    fn uint(&mut self) -> Option<&'a [u8]> {
+        let bytes = self.bytes()?;
+        if bytes.len() > 32 || bytes.first() == Some(&0) {
+            return None;
+        }
+        Some(bytes)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::hex;
+
+    const RECIPIENT: &str = "0x742d35cc6634c0532925a3b844bc454e4438f44e";
+
+    /// Base Sepolia, nonce 7, 0.01 ETH to RECIPIENT.
+    const NATIVE_TX: &str = "02f183014a3407843b9aca00847735940082520894742d35cc6634c0532925a3b8\
+        44bc454e4438f44e872386f26fc1000080c0";
+    const NATIVE_TX_HASH: &str = "5ab380a769b3c09384427714bee8d50433d6d6564744de85e60b1db8e43e70bb";
+
+    /// Base Sepolia, nonce 8, 1 USDC to RECIPIENT.
+    const ERC20_TX: &str = "02f86f83014a3408843b9aca00847735940082fde894036cbd53842c5426634e79\
+        29541ec2318f3dcf7e80b844a9059cbb000000000000000000000000742d35cc6634c0532925a3b844bc454e\
+        4438f44e00000000000000000000000000000000000000000000000000000000000f4240c0";
+    const ERC20_TX_HASH: &str = "46e60957ce07e5af6ddec75d0e625698f3d4292badd6ff23babc1c0b3d60f787";
+    const USDC: &str = "0x036cbd53842c5426634e7929541ec2318f3dcf7e";
+
+    #[test]
+    fn decodes_native_transfer() {
+        let decoded = decode(&hex(NATIVE_TX)).unwrap();
+        assert_eq!(
+            decoded.transfer,
+            Transfer {
+                chain_id: 84532,
+                asset_hash: keccak::hash(b"NATIVE").to_bytes(),
+                recipient_hash: keccak::hash(RECIPIENT.as_bytes()).to_bytes(),
+                amount: 10_000_000_000_000_000,
+            }
+        );
+        assert_eq!(decoded.message_digest.to_vec(), hex(NATIVE_TX_HASH));
+        assert_eq!(decoded.signature_scheme, SCHEME_ECDSA_KECCAK256);
+    }
+
+    #[test]
+    fn decodes_erc20_transfer() {
+        let decoded = decode(&hex(ERC20_TX)).unwrap();
+        assert_eq!(
+            decoded.transfer,
+            Transfer {
+                chain_id: 84532,
+                asset_hash: keccak::hash(USDC.as_bytes()).to_bytes(),
+                recipient_hash: keccak::hash(RECIPIENT.as_bytes()).to_bytes(),
+                amount: 1_000_000,
+            }
+        );
+        assert_eq!(decoded.message_digest.to_vec(), hex(ERC20_TX_HASH));
+    }
+
+    #[test]
+    fn rejects_malformed_transactions() {
+        let tx = hex(NATIVE_TX);
+        assert!(decode(&[]).is_none());
+        // Legacy transaction type
+        assert!(decode(&[&[0x01], &tx[1..]].concat()).is_none());
+        // Truncated, and with trailing bytes
+        assert!(decode(&tx[..tx.len() - 1]).is_none());
+        assert!(decode(&[tx.as_slice(), &[0x00]].concat()).is_none());
+        // Calldata that is not an ERC-20 transfer
+        let mut other_call = hex(ERC20_TX);
+        other_call[45] = 0x09; // selector
+        assert!(decode(&other_call).is_none());
+    }
+
+    #[test]
+    fn rejects_non_canonical_rlp() {
+        // Nonce 7 as a one-byte string instead of itself
+        let long_byte = hex(&NATIVE_TX.replacen("02f183014a3407", "02f283014a348107", 1));
+        assert!(decode(&long_byte).is_none());
+        // Nonce with a leading zero byte
+        let leading_zero = hex(&NATIVE_TX.replacen("02f183014a3407", "02f383014a34820007", 1));
+        assert!(decode(&leading_zero).is_none());
+        // Short list length in the long form
+        let long_len = hex(&NATIVE_TX.replacen("02f1", "02f831", 1));
+        assert!(decode(&long_len).is_none());
+    }
+}
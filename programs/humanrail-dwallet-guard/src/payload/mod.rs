@@ -0,0 +1,207 @@
+//! Raw transactions a signing request must carry.
+//!
+//! The guard decodes the transfer itself and rebuilds the message Ika is
+//! asked to sign, so the chain, asset, recipient and amount the policy is
+//! checked against cannot differ from what actually gets signed. A bare
+//! digest is only approved for the principal, and only once the principal
+//! has turned on `principal_digests`: the spend it is counted as cannot be
+//! verified, so turning it on is a loosening change held by the timelock.
+//!
+//! Ika keys a MessageApproval by `keccak256(message)` for every chain and
+//! signs `hash_scheme(message)`, so each decoder also fixes the signature
+//! scheme its message must be signed with (see the dWallet Developer Guide,
+//! "MessageApproval PDA").
+//!
+//! Bitcoin transactions and Solana messages carry no chain id, so those
+//! chains are given ids outside the EIP-155 range (SLIP-44 coin type with
+//! the BIP-44 hardened bit) and a payload is only decoded under an id of
+//! its own family.
+//!
+//! Asset and recipient hashes of decoded transfers are computed on-chain
+//! by each decoder; see the per-chain modules for the exact encoding.
+
+use anchor_lang::prelude::*;
+use crate::state::RejectionReason;
+
+pub mod bitcoin;
+pub mod evm;
+pub mod solana;
+
+/// Chain id of Bitcoin mainnet.
+pub const BITCOIN_MAINNET_CHAIN_ID: u32 = 0x8000_0000;
+/// Chain id of the Bitcoin test networks (testnet, signet, regtest).
+pub const BITCOIN_TESTNET_CHAIN_ID: u32 = 0x8000_0001;
+/// Chain id of Solana, every cluster.
+pub const SOLANA_CHAIN_ID: u32 = 0x8000_01f5;
+
+/// First chain id outside the EIP-155 range the guard accepts.
+const NON_EVM_CHAIN_IDS: u32 = 0x8000_0000;
+
+/// Which decoder a chain id belongs to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChainFamily {
+    Evm,
+    Bitcoin,
+    Solana,
+}
+
+impl ChainFamily {
+    /// Family of `chain_id`, or None for an unassigned id in the non-EVM
+    /// range.
+    pub fn of(chain_id: u32) -> Option<Self> {
+        match chain_id {
+            BITCOIN_MAINNET_CHAIN_ID | BITCOIN_TESTNET_CHAIN_ID => Some(ChainFamily::Bitcoin),
+            SOLANA_CHAIN_ID => Some(ChainFamily::Solana),
+            id if id < NON_EVM_CHAIN_IDS => Some(ChainFamily::Evm),
+            _ => None,
+        }
+    }
+}
+
+/// What approve_guarded_message is asked to sign.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum SigningPayload {
+    /// Bare digest. Rejected with PayloadRequired unless the principal
+    /// requests it on a guard with `principal_digests` on.
+    Digest,
+    /// Unsigned EIP-1559 transaction, `0x02 || rlp([...])`.
+    Evm { tx: Vec<u8> },
+    /// Unsigned segwit transaction, the input the dWallet signs and the
+    /// outputs spent by every input, in input order.
+    Bitcoin {
+        tx: Vec<u8>,
+        input_index: u32,
+        prevouts: Vec<bitcoin::Prevout>,
+    },
+    /// Serialized Solana message, for Curve25519 dWallets.
+    Solana { message: Vec<u8> },
+}
+
+/// A transfer in the terms the policy is written in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Transfer {
+    pub chain_id: u32,
+    pub asset_hash: [u8; 32],
+    pub recipient_hash: [u8; 32],
+    pub amount: u64,
+}
+
+/// What a decoder found in a payload.
+pub struct Decoded {
+    pub transfer: Transfer,
+    /// keccak256 of the message Ika signs
+    pub message_digest: [u8; 32],
+    pub signature_scheme: u16,
+}
+
+impl SigningPayload {
+    /// Checks that the payload encodes exactly `declared`, on a chain of
+    /// its own family, and is the message behind `message_digest`, to be
+    /// signed with `signature_scheme` by the dWallet key
+    /// `dwallet_public_key`. Returns the rejection reason, or None if so.
+    pub fn check(
+        &self,
+        message_digest: &[u8; 32],
+        signature_scheme: u16,
+        declared: &Transfer,
+        dwallet_public_key: &[u8],
+    ) -> RejectionReason {
+        let family = ChainFamily::of(declared.chain_id);
+        let decoded = match self {
+            SigningPayload::Digest => return RejectionReason::PayloadRequired,
+            SigningPayload::Evm { .. } if family != Some(ChainFamily::Evm) => {
+                return RejectionReason::PayloadMismatch
+            }
+            SigningPayload::Bitcoin { .. } if family != Some(ChainFamily::Bitcoin) => {
+                return RejectionReason::PayloadMismatch
+            }
+            SigningPayload::Solana { .. } if family != Some(ChainFamily::Solana) => {
+                return RejectionReason::PayloadMismatch
+            }
+            SigningPayload::Evm { tx } => evm::decode(tx),
+            SigningPayload::Bitcoin {
+                tx,
+                input_index,
+                prevouts,
+            } => bitcoin::decode(tx, *input_index, prevouts, declared.chain_id),
+            SigningPayload::Solana { message } => {
+                solana::decode(message, declared.chain_id, dwallet_public_key)
+            }
+        };
+        match decoded {
+            None => RejectionReason::InvalidPayload,
+            Some(decoded) => {
+                if &decoded.transfer == declared
+                    && &decoded.message_digest == message_digest
+                    && decoded.signature_scheme == signature_scheme
+                {
+                    RejectionReason::None
+                } else {
+                    RejectionReason::PayloadMismatch
+                }
+            }
+        }
+    }
+}
+
+/// Decodes a hex test vector.
+#[cfg(test)]
+fn hex(text: &str) -> Vec<u8> {
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain_families() {
+        assert_eq!(ChainFamily::of(1), Some(ChainFamily::Evm));
+        assert_eq!(ChainFamily::of(84532), Some(ChainFamily::Evm));
+        assert_eq!(ChainFamily::of(BITCOIN_MAINNET_CHAIN_ID), Some(ChainFamily::Bitcoin));
+        assert_eq!(ChainFamily::of(BITCOIN_TESTNET_CHAIN_ID), Some(ChainFamily::Bitcoin));
+        assert_eq!(ChainFamily::of(SOLANA_CHAIN_ID), Some(ChainFamily::Solana));
+        assert_eq!(ChainFamily::of(0x8000_0002), None);
+        assert_eq!(ChainFamily::of(u32::MAX), None);
+    }
+
+    #[test]
+    fn payloads_are_pinned_to_their_chain_family() {
+        let declared = |chain_id| Transfer {
+            chain_id,
+            asset_hash: [0; 32],
+            recipient_hash: [0; 32],
+            amount: 1,
+        };
+        let bitcoin = SigningPayload::Bitcoin {
+            tx: Vec::new(),
+            input_index: 0,
+            prevouts: Vec::new(),
+        };
+        let solana = SigningPayload::Solana {
+            message: Vec::new(),
+        };
+        let evm = SigningPayload::Evm { tx: Vec::new() };
+        for (payload, own_chain, other_chain) in [
+            (&bitcoin, BITCOIN_MAINNET_CHAIN_ID, 1),
+            (&solana, SOLANA_CHAIN_ID, BITCOIN_MAINNET_CHAIN_ID),
+            (&evm, 1, SOLANA_CHAIN_ID),
+        ] {
+            assert_eq!(
+                payload.check(&[0; 32], 0, &declared(other_chain), &[]),
+                RejectionReason::PayloadMismatch
+            );
+            assert_eq!(
+                payload.check(&[0; 32], 0, &declared(own_chain), &[]),
+                RejectionReason::InvalidPayload
+            );
+        }
+        assert_eq!(
+            SigningPayload::Digest.check(&[0; 32], 0, &declared(1), &[]),
+            RejectionReason::PayloadRequired
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! Solana message decoding for Curve25519 dWallets.
+//!
+//! Accepts a serialized legacy or v0 `Message` (v0 without address table
+//! lookups) whose instructions are all System `transfer` or SPL Token
+//! `transfer_checked`. The message Ika signs is the message itself, under
+//! Ed25519.
+//!
+//! Every transfer must be funded by the dWallet itself: the System
+//! transfer source, or the SPL transfer authority, is the dWallet's
+//! Ed25519 public key. Otherwise the signature would only be a fee payer's
+//! and the funds moved, never counted, would be someone else's.
+//!
+//! Every instruction must pay the same recipient in the same asset; their
+//! amounts are summed. The recipient hash is keccak256 of the raw 32-byte
+//! destination (the system account, or the token account for SPL), the
+//! asset hash is keccak256 of the raw mint, or keccak256("SOL") for SOL.
+//! Messages carry no chain id, so the declared Solana one is used.
+
+use anchor_lang::prelude::*;
+use solana_keccak_hasher as keccak;
+use super::{Decoded, Transfer};
+use crate::ika_cpi::SCHEME_EDDSA_SHA512;
+
+const SYSTEM_PROGRAM_ID: Pubkey = pubkey!("11111111111111111111111111111111");
+const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// System `Transfer { lamports }`: u32 tag 2, then u64.
+const SYSTEM_TRANSFER_TAG: [u8; 4] = [2, 0, 0, 0];
+const SYSTEM_TRANSFER_LEN: usize = 4 + 8;
+/// SPL Token `TransferChecked { amount, decimals }`: tag 12, u64, u8.
+const TOKEN_TRANSFER_CHECKED_TAG: u8 = 12;
+const TOKEN_TRANSFER_CHECKED_LEN: usize = 1 + 8 + 1;
+
+/// Asset name hashed for SOL.
+const NATIVE_ASSET: &[u8] = b"SOL";
+
+const VERSION_PREFIX: u8 = 0x80;
+
+/// Decodes `message`, or returns `None` if it is not a supported transfer
+/// out of `dwallet_key`.
+pub fn decode(message: &[u8], chain_id: u32, dwallet_key: &[u8]) -> Option<Decoded> {
+    let mut reader = Reader(message);
+
+    let versioned = message.first()? & VERSION_PREFIX != 0;
+    if versioned && reader.take(1)?[0] != VERSION_PREFIX {
+        return None;
+    }
+    let header = reader.take(3)?;
+    let key_count = reader.short_vec_len()?;
+    let keys: Vec<&[u8]> = (0..key_count)
+        .map(|_| reader.take(32))
+        .collect::<Option<_>>()?;
+    if usize::from(header[0]) > keys.len() {
+        return None;
+    }
+    reader.take(32)?; // recent blockhash
+
+    let instruction_count = reader.short_vec_len()?;
+    if instruction_count == 0 {
+        return None;
+    }
+    let mut transfer: Option<(&[u8], &[u8])> = None;
+    let mut amount = 0u64;
+    for _ in 0..instruction_count {
+        let program = *keys.get(usize::from(reader.take(1)?[0]))?;
+        let account_count = reader.short_vec_len()?;
+        let accounts = reader.take(account_count)?;
+        let data_len = reader.short_vec_len()?;
+        let data = reader.take(data_len)?;
+        let account = |position: usize| -> Option<&[u8]> {
+            keys.get(usize::from(*accounts.get(position)?)).copied()
+        };
+
+        let (asset, recipient, value) = if program == SYSTEM_PROGRAM_ID.as_ref() {
+            if accounts.len() != 2
+                || data.len() != SYSTEM_TRANSFER_LEN
+                || data[..4] != SYSTEM_TRANSFER_TAG
+            {
+                return None;
+            }
+            // source, destination
+            if account(0)? != dwallet_key {
+                return None;
+            }
+            (NATIVE_ASSET, account(1)?, read_u64(&data[4..])?)
+        } else if program == TOKEN_PROGRAM_ID.as_ref() {
+            // source, mint, destination, authority, then multisig signers
+            if accounts.len() < 4
+                || data.len() != TOKEN_TRANSFER_CHECKED_LEN
+                || data[0] != TOKEN_TRANSFER_CHECKED_TAG
+            {
+                return None;
+            }
+            if account(3)? != dwallet_key {
+                return None;
+            }
+            (account(1)?, account(2)?, read_u64(&data[1..9])?)
+        } else {
+            return None;
+        };
+
+        match transfer {
+            Some(first) if first != (asset, recipient) => return None,
+            _ => transfer = Some((asset, recipient)),
+        }
+        amount = amount.checked_add(value)?;
+    }
+
+    if versioned && reader.short_vec_len()? != 0 {
+        return None;
+    }
+    if !reader.0.is_empty() {
+        return None;
+    }
+
+    let (asset, recipient) = transfer?;
+    Some(Decoded {
+        transfer: Transfer {
+            chain_id,
+            asset_hash: keccak::hash(asset).to_bytes(),
+            recipient_hash: keccak::hash(recipient).to_bytes(),
+            amount,
+        },
+        message_digest: keccak::hash(message).to_bytes(),
+        signature_scheme: SCHEME_EDDSA_SHA512,
+    })
+}
+
+fn read_u64(bytes: &[u8]) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.get(..8)?.try_into().ok()?))
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(head)
+    }
+
+    /// A minimally encoded compact-u16 length.
+    fn short_vec_len(&mut self) -> Option<usize> {
+        let mut len = 0usize;
+        for i in 0..3 {
+            let byte = self.take(1)?[0];
+            len |= usize::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                if i > 0 && byte == 0 {
+                    return None;
+                }
+                return (len <= usize::from(u16::MAX)).then_some(len);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DWALLET: [u8; 32] = [0xaa; 32];
+    const RECIPIENT: [u8; 32] = [0xbb; 32];
+    const SOURCE_ATA: [u8; 32] = [0xdd; 32];
+    const MINT: [u8; 32] = [0xee; 32];
+    const CHAIN_ID: u32 = crate::payload::SOLANA_CHAIN_ID;
+
+    /// A compiled instruction: program index, account indexes, data.
+    type Ix = (u8, Vec<u8>, Vec<u8>);
+
+    /// Serializes a message with one signer, the first key. `v0` adds the
+    /// version prefix and an empty address table lookup list.
+    fn message(v0: bool, keys: &[[u8; 32]], instructions: &[Ix]) -> Vec<u8> {
+        let mut message = Vec::new();
+        if v0 {
+            message.push(VERSION_PREFIX);
+        }
+        message.extend_from_slice(&[1, 0, 1]);
+        message.push(keys.len() as u8);
+        for key in keys {
+            message.extend_from_slice(key);
+        }
+        message.extend_from_slice(&[0xcc; 32]);
+        message.push(instructions.len() as u8);
+        for (program, accounts, data) in instructions {
+            message.push(*program);
+            message.push(accounts.len() as u8);
+            message.extend_from_slice(accounts);
+            message.push(data.len() as u8);
+            message.extend_from_slice(data);
+        }
+        if v0 {
+            message.push(0);
+        }
+        message
+    }
+
+    fn system_transfer(from: u8, to: u8, lamports: u64) -> Ix {
+        let data = [&SYSTEM_TRANSFER_TAG[..], &lamports.to_le_bytes()].concat();
+        (2, vec![from, to], data)
+    }
+
+    fn system_keys() -> Vec<[u8; 32]> {
+        vec![DWALLET, RECIPIENT, SYSTEM_PROGRAM_ID.to_bytes()]
+    }
+
+    /// Keys: authority, source, mint, destination, token program.
+    fn token_transfer(authority: u8, amount: u64) -> Ix {
+        let data = [&[TOKEN_TRANSFER_CHECKED_TAG][..], &amount.to_le_bytes(), &[6]].concat();
+        (4, vec![1, 2, 3, authority], data)
+    }
+
+    fn token_keys() -> Vec<[u8; 32]> {
+        vec![DWALLET, SOURCE_ATA, MINT, RECIPIENT, TOKEN_PROGRAM_ID.to_bytes()]
+    }
+
+    #[test]
+    fn decodes_legacy_system_transfer() {
+        let message = message(false, &system_keys(), &[system_transfer(0, 1, 5_000_000)]);
+        let decoded = decode(&message, CHAIN_ID, &DWALLET).unwrap();
+        assert_eq!(
+            decoded.transfer,
+            Transfer {
+                chain_id: CHAIN_ID,
+                asset_hash: keccak::hash(b"SOL").to_bytes(),
+                recipient_hash: keccak::hash(&RECIPIENT).to_bytes(),
+                amount: 5_000_000,
+            }
+        );
+        assert_eq!(decoded.message_digest, keccak::hash(&message).to_bytes());
+        assert_eq!(decoded.signature_scheme, SCHEME_EDDSA_SHA512);
+    }
+
+    #[test]
+    fn decodes_v0_token_transfer() {
+        let message = message(true, &token_keys(), &[token_transfer(0, 2_500_000)]);
+        let decoded = decode(&message, CHAIN_ID, &DWALLET).unwrap();
+        assert_eq!(
+            decoded.transfer,
+            Transfer {
+                chain_id: CHAIN_ID,
+                asset_hash: keccak::hash(&MINT).to_bytes(),
+                recipient_hash: keccak::hash(&RECIPIENT).to_bytes(),
+                amount: 2_500_000,
+            }
+        );
+        assert_eq!(decoded.message_digest, keccak::hash(&message).to_bytes());
+    }
+
+    #[test]
+    fn sums_transfers_to_one_recipient() {
+        let transfers = [system_transfer(0, 1, 1_000), system_transfer(0, 1, 2_000)];
+        let message = message(false, &system_keys(), &transfers);
+        let decoded = decode(&message, CHAIN_ID, &DWALLET).unwrap();
+        assert_eq!(decoded.transfer.amount, 3_000);
+    }
+
+    #[test]
+    fn rejects_transfers_not_funded_by_the_dwallet() {
+        let other_source = message(false, &system_keys(), &[system_transfer(1, 0, 1_000)]);
+        assert!(decode(&other_source, CHAIN_ID, &DWALLET).is_none());
+        let other_authority = message(false, &token_keys(), &[token_transfer(1, 1_000)]);
+        assert!(decode(&other_authority, CHAIN_ID, &DWALLET).is_none());
+    }
+
+    #[test]
+    fn rejects_unsupported_messages() {
+        // Two recipients
+        let mut keys = system_keys();
+        keys.push([0x99; 32]);
+        let split = [system_transfer(0, 1, 1_000), system_transfer(0, 3, 1_000)];
+        assert!(decode(&message(false, &keys, &split), CHAIN_ID, &DWALLET).is_none());
+        // No instructions, or one for another program
+        assert!(decode(&message(false, &system_keys(), &[]), CHAIN_ID, &DWALLET).is_none());
+        let other_program = message(false, &system_keys(), &[(1, vec![0], vec![])]);
+        assert!(decode(&other_program, CHAIN_ID, &DWALLET).is_none());
+        // Address table lookups
+        let mut lookups = message(true, &system_keys(), &[system_transfer(0, 1, 1_000)]);
+        *lookups.last_mut().unwrap() = 1;
+        lookups.extend_from_slice(&[0x77; 32]);
+        lookups.extend_from_slice(&[0, 0]);
+        assert!(decode(&lookups, CHAIN_ID, &DWALLET).is_none());
+        // Unknown message version
+        let mut version = message(true, &system_keys(), &[system_transfer(0, 1, 1_000)]);
+        version[0] = VERSION_PREFIX | 1;
+        assert!(decode(&version, CHAIN_ID, &DWALLET).is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        let message = message(false, &system_keys(), &[system_transfer(0, 1, 1_000)]);
+        assert!(decode(&message, CHAIN_ID, &DWALLET).is_some());
+
+        assert!(decode(&[], CHAIN_ID, &DWALLET).is_none());
+        assert!(decode(&message[..message.len() - 1], CHAIN_ID, &DWALLET).is_none());
+        let trailing = [message.as_slice(), &[0]].concat();
+        assert!(decode(&trailing, CHAIN_ID, &DWALLET).is_none());
+        // Key count 3 as a two-byte compact-u16
+        let padded = [&message[..3], &[0x83, 0x00], &message[4..]].concat();
+        assert!(decode(&padded, CHAIN_ID, &DWALLET).is_none());
+    }
+}
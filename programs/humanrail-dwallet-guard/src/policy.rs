@@ -0,0 +1,231 @@
+//! Policy mutation rules shared by the direct principal instructions and the
+//! timelocked propose / execute flow.
+
+use anchor_lang::prelude::*;
+use crate::error::GuardError;
+use crate::ika_cpi::curve_schemes;
+use crate::state::{
+    AgentEntry, AssetLimits, GuardedDwallet, PolicyChange, TradingWindow, VelocityLimits,
+};
+
+impl AssetLimits {
+    /// True if `self` allows anything that `current` would have rejected.
+    pub fn loosens(&self, current: &AssetLimits) -> bool {
+        self.per_tx_limit > current.per_tx_limit
+            || self.daily_limit > current.daily_limit
+            || cap_loosened(self.hourly_limit, current.hourly_limit)
+            || cap_loosened(self.weekly_limit, current.weekly_limit)
+            || cap_loosened(self.monthly_limit, current.monthly_limit)
+            || cap_loosened(self.total_limit, current.total_limit)
+            || cap_loosened(self.cosign_threshold, current.cosign_threshold)
+    }
+}
+
+impl VelocityLimits {
+    pub fn loosens(&self, current: &VelocityLimits) -> bool {
+        cap_loosened(
+            self.max_approvals_per_hour.into(),
+            current.max_approvals_per_hour.into(),
+        ) || cap_loosened(
+            self.max_consecutive_rejections.into(),
+            current.max_consecutive_rejections.into(),
+        )
+    }
+}
+
+impl TradingWindow {
+    /// Any newly allowed hour or weekday loosens the window. Moving the
+    /// UTC offset shifts every allowed hour, so it counts as loosening too.
+    pub fn loosens(&self, current: &TradingWindow) -> bool {
+        self.allowed_hours & !current.allowed_hours != 0
+            || self.allowed_weekdays & !current.allowed_weekdays != 0
+            || self.utc_offset_secs != current.utc_offset_secs
+    }
+}
+
+impl AgentEntry {
+    pub fn loosens(&self, current: &AgentEntry) -> bool {
+        cap_loosened(self.per_tx_limit, current.per_tx_limit)
+            || cap_loosened(self.daily_limit, current.daily_limit)
+    }
+}
+
+/// For caps where 0 means unlimited.
+fn cap_loosened(new: u64, current: u64) -> bool {
+    current > 0 && (new == 0 || new > current)
+}
+
+impl GuardedDwallet {
+    /// Checks the limits, activation window and cooldown a policy is set
+    /// up with. Both init and UpdatePolicy go through it, so neither can
+    /// leave a guard that expires before it becomes usable.
+    pub fn validate_policy(
+        limits: &AssetLimits,
+        expires_at: i64,
+        not_before: i64,
+        min_interval_secs: i64,
+        now: i64,
+    ) -> Result<()> {
+        require!(expires_at > now, GuardError::InvalidExpiry);
+        require!(not_before < expires_at, GuardError::InvalidNotBefore);
+        require!(
+            (0..=Self::MAX_MIN_INTERVAL_SECS).contains(&min_interval_secs),
+            GuardError::InvalidMinInterval
+        );
+        limits.validate()
+    }
+
+    /// True if applying `change` would let through requests the current
+    /// policy rejects.
+    pub fn is_loosening(&self, change: &PolicyChange) -> bool {
+        match change {
+            PolicyChange::UpdatePolicy {
+                chain_id,
+                asset_hash,
+                limits,
+                expires_at,
+            } => {
+                let limits_loosened = match self.find_asset(*chain_id, asset_hash) {
+                    Some(asset) => limits.loosens(&asset.limits),
+                    None => true,
+                };
+                limits_loosened || *expires_at > self.expires_at
+            }
+            PolicyChange::AddChain { .. }
+            | PolicyChange::AddAsset { .. }
+            | PolicyChange::AddRecipient { .. } => true,
+            PolicyChange::SetPolicyChangeDelay { delay_secs } => {
+                *delay_secs < self.policy_change_delay
+            }
+            PolicyChange::SetVelocityLimits { limits } => limits.loosens(&self.velocity),
+            PolicyChange::SetTradingWindow { window } => window.loosens(&self.trading_window),
+            PolicyChange::AddCosigner { .. } => true,
+            // With no threshold set, requests above an asset's cosign
+            // threshold are rejected outright; setting one makes them
+            // approvable.
+            PolicyChange::SetCosignRequired { required } => {
+                *required < self.cosign_required || (self.cosign_required == 0 && *required > 0)
+            }
+            PolicyChange::AddAgent { .. } => true,
+            PolicyChange::SetAgentLimits {
+                agent,
+                per_tx_limit,
+                daily_limit,
+            } => match self.find_agent(agent) {
+                Some(current) => AgentEntry {
+                    per_tx_limit: *per_tx_limit,
+                    daily_limit: *daily_limit,
+                    ..*current
+                }
+                .loosens(current),
+                None => true,
+            },
+            PolicyChange::SetAllowedSchemes { schemes } => schemes & !self.allowed_schemes != 0,
+            PolicyChange::SetPrincipalDigests { allowed } => *allowed && !self.principal_digests,
+            PolicyChange::ReleaseDwallet { .. } => true,
+        }
+    }
+
+    /// Applies `change` and bumps `policy_nonce`. Callers are responsible for
+    /// enforcing the timelock.
+    pub fn apply_policy_change(&mut self, change: &PolicyChange, now: i64) -> Result<()> {
+        match *change {
+            PolicyChange::UpdatePolicy {
+                chain_id,
+                asset_hash,
+                limits,
+                expires_at,
+            } => {
+                Self::validate_policy(
+                    &limits,
+                    expires_at,
+                    self.not_before,
+                    self.min_interval_secs,
+                    now,
+                )?;
+                self.set_asset_limits(chain_id, &asset_hash, limits)?;
+                self.expires_at = expires_at;
+            }
+            PolicyChange::AddChain { chain_id } => self.add_chain(chain_id)?,
+            PolicyChange::AddAsset {
+                chain_id,
+                asset_hash,
+                limits,
+            } => self.add_asset(chain_id, asset_hash, limits)?,
+            PolicyChange::AddRecipient { entry } => self.add_recipient(entry)?,
+            PolicyChange::SetPolicyChangeDelay { delay_secs } => {
+                require!(
+                    (0..=Self::MAX_POLICY_CHANGE_DELAY).contains(&delay_secs),
+                    GuardError::InvalidPolicyChangeDelay
+                );
+                self.policy_change_delay = delay_secs;
+            }
+            PolicyChange::SetVelocityLimits { limits } => self.velocity = limits,
+            PolicyChange::SetTradingWindow { window } => {
+                window.validate()?;
+                self.trading_window = window;
+            }
+            PolicyChange::AddCosigner { cosigner } => self.add_cosigner(cosigner)?,
+            PolicyChange::SetCosignRequired { required } => self.set_cosign_required(required)?,
+            PolicyChange::AddAgent { entry } => self.add_agent(entry)?,
+            PolicyChange::SetAgentLimits {
+                agent,
+                per_tx_limit,
+                daily_limit,
+            } => self.set_agent_limits(&agent, per_tx_limit, daily_limit)?,
+            PolicyChange::SetAllowedSchemes { schemes } => {
+                let supported = curve_schemes(self.curve).unwrap_or(0);
+                require!(
+                    schemes != 0 && schemes & !supported == 0,
+                    GuardError::InvalidSignatureSchemes
+                );
+                self.allowed_schemes = schemes;
+            }
+            PolicyChange::SetPrincipalDigests { allowed } => self.principal_digests = allowed,
+            PolicyChange::ReleaseDwallet { new_authority } => {
+                require!(
+                    new_authority != Pubkey::default(),
+                    GuardError::InvalidReleaseAuthority
+                );
+                self.release_authority = new_authority;
+            }
+        }
+        self.bump_policy_nonce();
+        Ok(())
+    }
+
+    /// Applies `change` right away if it tightens the policy or the guard
+    /// has no timelock configured.
+    pub fn apply_policy_change_now(&mut self, change: &PolicyChange, now: i64) -> Result<()> {
+        require!(
+            self.policy_change_delay == 0 || !self.is_loosening(change),
+            GuardError::PolicyChangeTimelocked
+        );
+        self.apply_policy_change(change, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_710_115_200;
+
+    #[test]
+    fn policy_must_be_usable_before_it_expires() {
+        let limits = AssetLimits {
+            per_tx_limit: 10,
+            daily_limit: 100,
+            ..AssetLimits::default()
+        };
+        let validate = |expires_at, not_before, min_interval_secs| {
+            GuardedDwallet::validate_policy(&limits, expires_at, not_before, min_interval_secs, NOW)
+        };
+        assert!(validate(NOW + 60, 0, 0).is_ok());
+        assert!(validate(NOW + 60, NOW + 59, GuardedDwallet::MAX_MIN_INTERVAL_SECS).is_ok());
+        assert!(validate(NOW, 0, 0).is_err());
+        assert!(validate(NOW + 60, NOW + 60, 0).is_err());
+        assert!(validate(NOW + 60, 0, -1).is_err());
+        assert!(validate(NOW + 60, 0, GuardedDwallet::MAX_MIN_INTERVAL_SECS + 1).is_err());
+    }
+}
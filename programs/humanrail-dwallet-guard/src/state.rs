@@ -1,36 +1,994 @@
 use anchor_lang::prelude::*;
+use crate::error::GuardError;
 
-/// GuardedDwallet stores the policy for a single dWallet + agent pair.
+/// GuardedDwallet stores the policy for a single dWallet and every agent
+/// allowed to drive it. One per dWallet, so all agents share its limits.
 /// It is owned by the HumanRail dWallet Guard program.
 #[account]
 pub struct GuardedDwallet {
     pub version: u8,
     pub principal: Pubkey,
     pub human_profile: Pubkey,
-    pub agent: Pubkey,
-    pub humanrail_capability: Pubkey,
     pub dwallet: Pubkey,
-    pub allowed_chain_id: u32,
-    pub allowed_asset_hash: [u8; 32],
-    pub allowed_recipient_hash: [u8; 32],
+    pub expires_at: i64,
+    pub frozen: bool,
+    pub bump: u8,
+    /// Incremented on every principal policy change so off-chain systems
+    /// can detect that the policy they cached is stale.
+    pub policy_nonce: u64,
+    /// Seconds a loosening policy change must wait between
+    /// propose_policy_change and execute_policy_change. 0 disables the timelock.
+    pub policy_change_delay: i64,
+    /// Approved requests whose Ika MessageApproval has not been confirmed
+    /// as signed yet. release_dwallet is refused while this is non-zero.
+    pub outstanding_approvals: u32,
+    /// Requests held as PendingCosign. close_guarded_dwallet is refused
+    /// while this is non-zero.
+    pub pending_cosigns: u32,
+    /// Created by initialize_guarded_dwallet_demo. Demo guards never linked
+    /// real HumanRail accounts, so approve skips the delegation re-check.
+    pub demo_mode: bool,
+    /// How the daily period is accounted; chosen at init and fixed afterwards.
+    pub daily_window: DailyWindow,
+    pub velocity: VelocityLimits,
+    /// Approvals in the current UTC hour, for `max_approvals_per_hour`.
+    pub hourly_approvals: PeriodCounter,
+    /// Rejections since the last approval or unfreeze.
+    pub consecutive_rejections: u32,
+    pub trading_window: TradingWindow,
+    /// Requests are rejected before this time, so a guard can be set up
+    /// ahead of when the agent may use it.
+    pub not_before: i64,
+    /// Minimum seconds between consecutive approvals. 0 disables the cooldown.
+    pub min_interval_secs: i64,
+    /// Time of the last approval; 0 before the first one.
+    pub last_approval_at: i64,
+    /// Co-signer approvals needed for requests above an asset's
+    /// `cosign_threshold`. 0 means such requests are rejected.
+    pub cosign_required: u8,
+    /// Key the principal role is being handed to; default when no
+    /// transfer is pending.
+    pub pending_principal: Pubkey,
+    /// DWalletCurve of the dWallet, read from its account at init.
+    pub curve: u16,
+    /// Signature schemes requests may use, bit n = DWalletSignatureScheme n.
+    /// Always a subset of what `curve` can produce.
+    pub allowed_schemes: u8,
+    /// Whether the principal may have a bare digest approved. Off at init;
+    /// turning it on is a loosening change.
+    pub principal_digests: bool,
+    /// Whether the dWallet's authority is this program's CPI authority, so
+    /// approvals can reach Ika. Set by arm, or at init for a dWallet the CPI
+    /// authority already holds; cleared on release.
+    pub armed: bool,
+    /// Who held the dWallet's authority before handing it to the CPI
+    /// authority: the principal at init or after a principal transfer, or
+    /// the release target after a release. Only this key can arm the guard.
+    pub pre_arm_authority: Pubkey,
+    /// Key release_dwallet may hand the dWallet to, recorded by a
+    /// ReleaseDwallet policy change; default when none has been applied
+    /// or the release it allowed has happened.
+    pub release_authority: Pubkey,
+    /// Allowed destination chains. The bounded lists below are Borsh vecs
+    /// (u32 length, then the entries) laid out back to back, so only the
+    /// first starts at a fixed offset; off-chain parsers have to walk them
+    /// in declaration order.
+    pub allowed_chain_ids: Vec<u32>,
+    /// Per-chain, per-asset limits and spend counters.
+    pub assets: Vec<AssetPolicy>,
+    /// Per-chain allowlisted recipients.
+    pub recipients: Vec<RecipientEntry>,
+    /// Keys that may co-sign requests held as PendingCosign.
+    pub cosigners: Vec<Pubkey>,
+    /// Agents allowed to request signatures, each with its own sub-limits.
+    pub agents: Vec<AgentEntry>,
+    /// Per-agent, per-asset spend counters for the agent sub-limits.
+    pub agent_spend: Vec<AgentSpend>,
+    /// Keys that may freeze the guard, but not unfreeze it or change policy.
+    pub guardians: Vec<Pubkey>,
+}
+
+impl GuardedDwallet {
+    /// Layout version written at init. Version 1 guards (one per principal,
+    /// agent and dWallet) live at other seeds and are not loaded.
+    pub const VERSION: u8 = 2;
+    pub const MAX_POLICY_CHANGE_DELAY: i64 = 30 * 86400;
+    pub const MAX_MIN_INTERVAL_SECS: i64 = 86400;
+    pub const MAX_CHAINS: usize = 8;
+    pub const MAX_ASSETS: usize = 8;
+    pub const MAX_RECIPIENTS: usize = 16;
+    pub const MAX_COSIGNERS: usize = 8;
+    pub const MAX_AGENTS: usize = 4;
+    /// Agent/asset pairs with spend counters; idle ones are recycled.
+    pub const MAX_AGENT_SPEND: usize = 16;
+    pub const MAX_GUARDIANS: usize = 4;
+
+    // Fixed fields padded to 360, plus the bounded lists
+    // (4-byte vec length prefix + max entries each).
+    pub const LEN: usize = 360
+        + 4 + Self::MAX_CHAINS * 4
+        + 4 + Self::MAX_ASSETS * AssetPolicy::LEN
+        + 4 + Self::MAX_RECIPIENTS * RecipientEntry::LEN
+        + 4 + Self::MAX_COSIGNERS * 32
+        + 4 + Self::MAX_AGENTS * AgentEntry::LEN
+        + 4 + Self::MAX_AGENT_SPEND * AgentSpend::LEN
+        + 4 + Self::MAX_GUARDIANS * 32;
+
+    /// Takes a request out of PendingCosign, whether it was co-signed to
+    /// a decision or closed.
+    pub fn release_pending_cosign(&mut self) {
+        self.pending_cosigns = self.pending_cosigns.saturating_sub(1);
+    }
+
+    pub fn bump_policy_nonce(&mut self) {
+        self.policy_nonce = self.policy_nonce.wrapping_add(1);
+    }
+
+    /// Counts an approved request: its spend (also against the requesting
+    /// agent's sub-limits), the outstanding approval awaiting Ika, and the
+    /// velocity counters.
+    pub fn record_approval(
+        &mut self,
+        agent: Option<&Pubkey>,
+        chain_id: u32,
+        asset_hash: &[u8; 32],
+        amount: u64,
+        now: i64,
+    ) -> Result<()> {
+        self.find_asset_mut(chain_id, asset_hash)
+            .ok_or(GuardError::AssetNotAllowed)?
+            .record_spend(amount, now);
+        if let Some(agent) = agent {
+            self.record_agent_spend(agent, chain_id, asset_hash, amount, now)?;
+        }
+        self.outstanding_approvals = self.outstanding_approvals.saturating_add(1);
+        self.consecutive_rejections = 0;
+        self.last_approval_at = now;
+        self.hourly_approvals.record(SpendPeriod::Hourly, 1, now);
+        Ok(())
+    }
+
+    /// Counts a rejected request and freezes the guard once
+    /// `max_consecutive_rejections` is reached. Returns true if this call
+    /// froze it.
+    pub fn record_rejection(&mut self) -> bool {
+        self.consecutive_rejections = self.consecutive_rejections.saturating_add(1);
+        let limit = self.velocity.max_consecutive_rejections;
+        if self.frozen || limit == 0 || self.consecutive_rejections < limit {
+            return false;
+        }
+        self.frozen = true;
+        true
+    }
+
+    pub fn is_chain_allowed(&self, chain_id: u32) -> bool {
+        self.allowed_chain_ids.contains(&chain_id)
+    }
+
+    pub fn add_chain(&mut self, chain_id: u32) -> Result<()> {
+        require!(!self.is_chain_allowed(chain_id), GuardError::ChainAlreadyAllowed);
+        require!(
+            self.allowed_chain_ids.len() < Self::MAX_CHAINS,
+            GuardError::ChainListFull
+        );
+        self.allowed_chain_ids.push(chain_id);
+        Ok(())
+    }
+
+    /// Removes a chain together with every asset, recipient and agent
+    /// counter scoped to it.
+    pub fn remove_chain(&mut self, chain_id: u32) -> Result<()> {
+        require!(self.is_chain_allowed(chain_id), GuardError::ChainNotAllowed);
+        self.allowed_chain_ids.retain(|id| *id != chain_id);
+        self.assets.retain(|entry| entry.chain_id != chain_id);
+        self.recipients.retain(|entry| entry.chain_id != chain_id);
+        self.agent_spend.retain(|entry| entry.chain_id != chain_id);
+        Ok(())
+    }
+
+    pub fn find_asset(&self, chain_id: u32, asset_hash: &[u8; 32]) -> Option<&AssetPolicy> {
+        self.assets
+            .iter()
+            .find(|entry| entry.chain_id == chain_id && &entry.asset_hash == asset_hash)
+    }
+
+    pub fn find_asset_mut(
+        &mut self,
+        chain_id: u32,
+        asset_hash: &[u8; 32],
+    ) -> Option<&mut AssetPolicy> {
+        self.assets
+            .iter_mut()
+            .find(|entry| entry.chain_id == chain_id && &entry.asset_hash == asset_hash)
+    }
+
+    pub fn add_asset(
+        &mut self,
+        chain_id: u32,
+        asset_hash: [u8; 32],
+        limits: AssetLimits,
+    ) -> Result<()> {
+        limits.validate()?;
+        require!(self.is_chain_allowed(chain_id), GuardError::ChainNotAllowed);
+        require!(
+            self.find_asset(chain_id, &asset_hash).is_none(),
+            GuardError::AssetPolicyAlreadyExists
+        );
+        require!(
+            self.assets.len() < Self::MAX_ASSETS,
+            GuardError::AssetPolicyListFull
+        );
+        self.assets.push(AssetPolicy::new(chain_id, asset_hash, limits));
+        Ok(())
+    }
+
+    /// Replaces the limits of an existing asset entry, keeping its spend counters.
+    pub fn set_asset_limits(
+        &mut self,
+        chain_id: u32,
+        asset_hash: &[u8; 32],
+        limits: AssetLimits,
+    ) -> Result<()> {
+        limits.validate()?;
+        let asset = self
+            .find_asset_mut(chain_id, asset_hash)
+            .ok_or(GuardError::AssetNotAllowed)?;
+        asset.limits = limits;
+        Ok(())
+    }
+
+    pub fn remove_asset(&mut self, chain_id: u32, asset_hash: &[u8; 32]) -> Result<()> {
+        let index = self
+            .assets
+            .iter()
+            .position(|entry| entry.chain_id == chain_id && &entry.asset_hash == asset_hash)
+            .ok_or(GuardError::AssetNotAllowed)?;
+        self.assets.remove(index);
+        self.agent_spend
+            .retain(|entry| !(entry.chain_id == chain_id && &entry.asset_hash == asset_hash));
+        Ok(())
+    }
+
+    pub fn find_recipient(
+        &self,
+        chain_id: u32,
+        recipient_hash: &[u8; 32],
+    ) -> Option<&RecipientEntry> {
+        self.recipients
+            .iter()
+            .find(|entry| entry.chain_id == chain_id && &entry.recipient_hash == recipient_hash)
+    }
+
+    pub fn add_recipient(&mut self, entry: RecipientEntry) -> Result<()> {
+        require!(self.is_chain_allowed(entry.chain_id), GuardError::ChainNotAllowed);
+        require!(
+            self.find_recipient(entry.chain_id, &entry.recipient_hash).is_none(),
+            GuardError::RecipientAlreadyAllowed
+        );
+        require!(
+            self.recipients.len() < Self::MAX_RECIPIENTS,
+            GuardError::RecipientListFull
+        );
+        self.recipients.push(entry);
+        Ok(())
+    }
+
+    pub fn remove_recipient(&mut self, chain_id: u32, recipient_hash: &[u8; 32]) -> Result<()> {
+        let index = self
+            .recipients
+            .iter()
+            .position(|entry| entry.chain_id == chain_id && &entry.recipient_hash == recipient_hash)
+            .ok_or(GuardError::RecipientNotAllowed)?;
+        self.recipients.remove(index);
+        Ok(())
+    }
+
+    pub fn find_agent(&self, agent: &Pubkey) -> Option<&AgentEntry> {
+        self.agents.iter().find(|entry| &entry.agent == agent)
+    }
+
+    pub fn add_agent(&mut self, entry: AgentEntry) -> Result<()> {
+        entry.validate()?;
+        require!(
+            self.find_agent(&entry.agent).is_none(),
+            GuardError::AgentAlreadyAdded
+        );
+        require!(self.agents.len() < Self::MAX_AGENTS, GuardError::AgentListFull);
+        self.agents.push(entry);
+        Ok(())
+    }
+
+    /// Removes an agent together with its spend counters.
+    pub fn remove_agent(&mut self, agent: &Pubkey) -> Result<()> {
+        let index = self
+            .agents
+            .iter()
+            .position(|entry| &entry.agent == agent)
+            .ok_or(GuardError::AgentNotFound)?;
+        self.agents.remove(index);
+        self.agent_spend.retain(|entry| &entry.agent != agent);
+        Ok(())
+    }
+
+    pub fn set_agent_limits(
+        &mut self,
+        agent: &Pubkey,
+        per_tx_limit: u64,
+        daily_limit: u64,
+    ) -> Result<()> {
+        let entry = self
+            .agents
+            .iter_mut()
+            .find(|entry| &entry.agent == agent)
+            .ok_or(GuardError::AgentNotFound)?;
+        let updated = AgentEntry {
+            per_tx_limit,
+            daily_limit,
+            ..*entry
+        };
+        updated.validate()?;
+        *entry = updated;
+        Ok(())
+    }
+
+    /// What `agent` has spent of one asset in the current daily period,
+    /// accounted with the guard's `daily_window`.
+    pub fn agent_daily_spent_at(
+        &self,
+        agent: &Pubkey,
+        chain_id: u32,
+        asset_hash: &[u8; 32],
+        now: i64,
+    ) -> u64 {
+        self.agent_spend
+            .iter()
+            .find(|entry| entry.matches(agent, chain_id, asset_hash))
+            .map(|entry| entry.spent_at(now, self.daily_window))
+            .unwrap_or(0)
+    }
+
+    fn record_agent_spend(
+        &mut self,
+        agent: &Pubkey,
+        chain_id: u32,
+        asset_hash: &[u8; 32],
+        amount: u64,
+        now: i64,
+    ) -> Result<()> {
+        let existing = self
+            .agent_spend
+            .iter()
+            .position(|entry| entry.matches(agent, chain_id, asset_hash));
+        let index = match existing {
+            Some(index) => index,
+            None => {
+                let fresh = AgentSpend {
+                    agent: *agent,
+                    chain_id,
+                    asset_hash: *asset_hash,
+                    daily: RollingDay::default(),
+                };
+                // Once the list is full, reuse an entry with nothing spent
+                // in the last 24 hours; it no longer counts against anything.
+                if self.agent_spend.len() < Self::MAX_AGENT_SPEND {
+                    self.agent_spend.push(fresh);
+                    self.agent_spend.len() - 1
+                } else {
+                    let idle = self
+                        .agent_spend
+                        .iter()
+                        .position(|entry| entry.daily.is_idle(now))
+                        .ok_or(GuardError::AgentListFull)?;
+                    self.agent_spend[idle] = fresh;
+                    idle
+                }
+            }
+        };
+        self.agent_spend[index].daily.record(amount, now);
+        Ok(())
+    }
+
+    pub fn is_cosigner(&self, key: &Pubkey) -> bool {
+        self.cosigners.contains(key)
+    }
+
+    pub fn add_cosigner(&mut self, cosigner: Pubkey) -> Result<()> {
+        require!(!self.is_cosigner(&cosigner), GuardError::CosignerAlreadyAdded);
+        require!(
+            self.cosigners.len() < Self::MAX_COSIGNERS,
+            GuardError::CosignerListFull
+        );
+        self.cosigners.push(cosigner);
+        Ok(())
+    }
+
+    /// Refused if it would leave fewer co-signers than `cosign_required`.
+    pub fn remove_cosigner(&mut self, cosigner: &Pubkey) -> Result<()> {
+        let index = self
+            .cosigners
+            .iter()
+            .position(|key| key == cosigner)
+            .ok_or(GuardError::CosignerNotFound)?;
+        require!(
+            self.cosigners.len() > usize::from(self.cosign_required),
+            GuardError::InvalidCosignRequired
+        );
+        self.cosigners.remove(index);
+        Ok(())
+    }
+
+    pub fn set_cosign_required(&mut self, required: u8) -> Result<()> {
+        require!(
+            usize::from(required) <= self.cosigners.len(),
+            GuardError::InvalidCosignRequired
+        );
+        self.cosign_required = required;
+        Ok(())
+    }
+
+    /// Co-signatures on `request` from keys that are still co-signers.
+    pub fn cosign_count(&self, request: &GuardSigningRequest) -> usize {
+        request
+            .cosigned_by
+            .iter()
+            .filter(|key| self.is_cosigner(key))
+            .count()
+    }
+
+    /// The principal or a guardian; the keys allowed to freeze.
+    pub fn can_freeze(&self, key: &Pubkey) -> bool {
+        &self.principal == key || self.guardians.contains(key)
+    }
+
+    pub fn add_guardian(&mut self, guardian: Pubkey) -> Result<()> {
+        require!(
+            !self.guardians.contains(&guardian),
+            GuardError::GuardianAlreadyAdded
+        );
+        require!(
+            self.guardians.len() < Self::MAX_GUARDIANS,
+            GuardError::GuardianListFull
+        );
+        self.guardians.push(guardian);
+        Ok(())
+    }
+
+    pub fn remove_guardian(&mut self, guardian: &Pubkey) -> Result<()> {
+        let index = self
+            .guardians
+            .iter()
+            .position(|key| key == guardian)
+            .ok_or(GuardError::GuardianNotFound)?;
+        self.guardians.remove(index);
+        Ok(())
+    }
+}
+
+/// Limits on how often requests are approved or rejected, independent of
+/// their value. 0 disables a limit.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct VelocityLimits {
+    pub max_approvals_per_hour: u32,
+    /// Consecutive rejections after which approve freezes the guard.
+    pub max_consecutive_rejections: u32,
+}
+
+/// Local hours and weekdays during which requests may be approved.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct TradingWindow {
+    /// Bit `h` allows local hour `h` (0..=23).
+    pub allowed_hours: u32,
+    /// Bit 0 = Monday through bit 6 = Sunday, in local time.
+    pub allowed_weekdays: u8,
+    /// Offset of local time from UTC, in seconds.
+    pub utc_offset_secs: i32,
+}
+
+impl TradingWindow {
+    pub const ALL_HOURS: u32 = (1 << 24) - 1;
+    pub const ALL_WEEKDAYS: u8 = (1 << 7) - 1;
+    pub const MAX_UTC_OFFSET_SECS: i32 = 14 * 3600;
+
+    /// No restriction: every hour of every day, UTC.
+    pub const ALWAYS: TradingWindow = TradingWindow {
+        allowed_hours: Self::ALL_HOURS,
+        allowed_weekdays: Self::ALL_WEEKDAYS,
+        utc_offset_secs: 0,
+    };
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.allowed_hours != 0 && self.allowed_hours & !Self::ALL_HOURS == 0,
+            GuardError::InvalidTradingWindow
+        );
+        require!(
+            self.allowed_weekdays != 0 && self.allowed_weekdays & !Self::ALL_WEEKDAYS == 0,
+            GuardError::InvalidTradingWindow
+        );
+        require!(
+            self.utc_offset_secs.abs() <= Self::MAX_UTC_OFFSET_SECS,
+            GuardError::InvalidTradingWindow
+        );
+        Ok(())
+    }
+
+    pub fn contains(&self, now: i64) -> bool {
+        let local = now + i64::from(self.utc_offset_secs);
+        let hour = local.rem_euclid(86400) / 3600;
+        // 1970-01-01 was a Thursday (bit 3)
+        let weekday = (local.div_euclid(86400) + 3).rem_euclid(7);
+        self.allowed_hours & (1 << hour) != 0 && self.allowed_weekdays & (1 << weekday) != 0
+    }
+}
+
+/// Accounting window for `AssetLimits::daily_limit` and the agents' daily
+/// sub-limits.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DailyWindow {
+    /// Resets at each UTC midnight.
+    CalendarDay,
+    /// Sum of the last 24 hourly buckets, so the limit cannot be spent
+    /// twice across midnight.
+    Rolling24h,
+}
+
+/// Spend limits for one asset, in that asset's own base units.
+/// `per_tx_limit` and `daily_limit` are required; every other cap of 0
+/// means unlimited.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct AssetLimits {
     pub per_tx_limit: u64,
     pub daily_limit: u64,
     pub total_limit: u64,
-    pub daily_spent: u64,
+    pub hourly_limit: u64,
+    pub weekly_limit: u64,
+    pub monthly_limit: u64,
+    /// Amounts above this wait for co-signers instead of being approved
+    /// directly. 0 disables co-signing for the asset.
+    pub cosign_threshold: u64,
+}
+
+impl AssetLimits {
+    pub const LEN: usize = 8 * 7;
+
+    /// Requires per-tx and daily limits, every configured cap to be at
+    /// least as large as the caps of the shorter periods below it, and the
+    /// co-sign threshold to be within the per-tx limit.
+    pub fn validate(&self) -> Result<()> {
+        require!(self.per_tx_limit > 0, GuardError::InvalidLimitConfig);
+        require!(self.daily_limit > 0, GuardError::InvalidLimitConfig);
+        let mut floor = self.per_tx_limit;
+        for cap in [
+            self.hourly_limit,
+            self.daily_limit,
+            self.weekly_limit,
+            self.monthly_limit,
+            self.total_limit,
+        ] {
+            if cap > 0 {
+                require!(cap >= floor, GuardError::InvalidLimitConfig);
+                floor = cap;
+            }
+        }
+        require!(
+            self.cosign_threshold <= self.per_tx_limit,
+            GuardError::InvalidLimitConfig
+        );
+        Ok(())
+    }
+
+    pub fn period_limit(&self, period: SpendPeriod) -> u64 {
+        match period {
+            SpendPeriod::Hourly => self.hourly_limit,
+            SpendPeriod::Daily => self.daily_limit,
+            SpendPeriod::Weekly => self.weekly_limit,
+            SpendPeriod::Monthly => self.monthly_limit,
+        }
+    }
+}
+
+/// Recurring spend window with its own limit and counter.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpendPeriod {
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl SpendPeriod {
+    pub const ALL: [SpendPeriod; 4] = [
+        SpendPeriod::Hourly,
+        SpendPeriod::Daily,
+        SpendPeriod::Weekly,
+        SpendPeriod::Monthly,
+    ];
+
+    /// Index of the UTC period containing `now`. Weeks start on Monday,
+    /// months are calendar months.
+    pub fn epoch(self, now: i64) -> i64 {
+        let days = now.div_euclid(86400);
+        match self {
+            SpendPeriod::Hourly => now.div_euclid(3600),
+            SpendPeriod::Daily => days,
+            // 1970-01-01 was a Thursday
+            SpendPeriod::Weekly => (days + 3).div_euclid(7),
+            SpendPeriod::Monthly => month_index(days),
+        }
+    }
+
+    pub fn rejection(self) -> RejectionReason {
+        match self {
+            SpendPeriod::Hourly => RejectionReason::HourlyLimitExceeded,
+            SpendPeriod::Daily => RejectionReason::DailyLimitExceeded,
+            SpendPeriod::Weekly => RejectionReason::WeeklyLimitExceeded,
+            SpendPeriod::Monthly => RejectionReason::MonthlyLimitExceeded,
+        }
+    }
+}
+
+/// Months since year 0 for a count of days since the Unix epoch
+/// (civil-from-days, proleptic Gregorian calendar).
+fn month_index(days: i64) -> i64 {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    year * 12 + (month - 1)
+}
+
+/// Spend per UTC hour over the last 24 hours, for `DailyWindow::Rolling24h`.
+/// Buckets are indexed by `hour % 24`; those after `last_hour` are stale.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct RollingDay {
+    pub buckets: [u64; 24],
+    pub last_hour: i64,
+}
+
+impl RollingDay {
+    pub const LEN: usize = 24 * 8 + 8;
+
+    /// Sum of the buckets inside the 24 hours ending at `now`.
+    pub fn spent_at(&self, now: i64) -> u64 {
+        self.spent_since(now / 3600 - 23, now)
+    }
+
+    /// Sum of the buckets since the UTC midnight before `now`.
+    pub fn spent_today(&self, now: i64) -> u64 {
+        self.spent_since(now.div_euclid(86400) * 24, now)
+    }
+
+    /// True if nothing was recorded in the 24 hours ending at `now`.
+    pub fn is_idle(&self, now: i64) -> bool {
+        (now / 3600).saturating_sub(self.last_hour) >= 24
+    }
+
+    /// Sum of the buckets from `first_hour`, which must be within the 24
+    /// hours ending at `now`.
+    fn spent_since(&self, first_hour: i64, now: i64) -> u64 {
+        if self.is_idle(now) {
+            return 0;
+        }
+        (first_hour..=self.last_hour)
+            .map(|h| self.buckets[h.rem_euclid(24) as usize])
+            .fold(0u64, |acc, spent| acc.saturating_add(spent))
+    }
+
+    /// Clears the buckets that fell out of the window, then adds `amount`
+    /// to the current hour.
+    pub fn record(&mut self, amount: u64, now: i64) {
+        let hour = now / 3600;
+        if self.is_idle(now) {
+            self.buckets = [0; 24];
+        } else {
+            for h in self.last_hour + 1..=hour {
+                self.buckets[h.rem_euclid(24) as usize] = 0;
+            }
+        }
+        let bucket = &mut self.buckets[hour.rem_euclid(24) as usize];
+        *bucket = bucket.saturating_add(amount);
+        self.last_hour = hour;
+    }
+}
+
+/// Spend within one period, reset whenever the period's epoch changes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct PeriodCounter {
+    pub spent: u64,
+    pub epoch: i64,
+}
+
+impl PeriodCounter {
+    pub const LEN: usize = 8 + 8;
+
+    pub fn spent_at(&self, period: SpendPeriod, now: i64) -> u64 {
+        if period.epoch(now) != self.epoch {
+            0
+        } else {
+            self.spent
+        }
+    }
+
+    pub fn record(&mut self, period: SpendPeriod, amount: u64, now: i64) {
+        self.spent = self.spent_at(period, now).saturating_add(amount);
+        self.epoch = period.epoch(now);
+    }
+}
+
+/// Policy entry for a single asset on a single chain: its limits plus the
+/// spend counters those limits are checked against.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct AssetPolicy {
+    pub chain_id: u32,
+    pub asset_hash: [u8; 32],
+    pub limits: AssetLimits,
+    pub hourly: PeriodCounter,
+    pub daily: PeriodCounter,
+    pub weekly: PeriodCounter,
+    pub monthly: PeriodCounter,
     pub total_spent: u64,
-    pub last_spend_day: i64,
-    pub expires_at: i64,
-    pub frozen: bool,
+    pub rolling: RollingDay,
+}
+
+impl AssetPolicy {
+    pub const LEN: usize = 4 + 32 + AssetLimits::LEN + 4 * PeriodCounter::LEN + 8 + RollingDay::LEN;
+
+    pub fn new(chain_id: u32, asset_hash: [u8; 32], limits: AssetLimits) -> Self {
+        Self {
+            chain_id,
+            asset_hash,
+            limits,
+            ..Self::default()
+        }
+    }
+
+    fn counter(&self, period: SpendPeriod) -> &PeriodCounter {
+        match period {
+            SpendPeriod::Hourly => &self.hourly,
+            SpendPeriod::Daily => &self.daily,
+            SpendPeriod::Weekly => &self.weekly,
+            SpendPeriod::Monthly => &self.monthly,
+        }
+    }
+
+    /// Spend counted against the `period` limit as of `now`. The daily
+    /// period follows `window`; the others are always calendar periods.
+    pub fn spent_at(&self, period: SpendPeriod, now: i64, window: DailyWindow) -> u64 {
+        if period == SpendPeriod::Daily && window == DailyWindow::Rolling24h {
+            return self.rolling.spent_at(now);
+        }
+        self.counter(period).spent_at(period, now)
+    }
+
+    /// Updates every period counter and the rolling buckets, so either
+    /// daily window reads a consistent value.
+    pub fn record_spend(&mut self, amount: u64, now: i64) {
+        self.hourly.record(SpendPeriod::Hourly, amount, now);
+        self.daily.record(SpendPeriod::Daily, amount, now);
+        self.weekly.record(SpendPeriod::Weekly, amount, now);
+        self.monthly.record(SpendPeriod::Monthly, amount, now);
+        self.rolling.record(amount, now);
+        self.total_spent = self.total_spent.saturating_add(amount);
+    }
+}
+
+/// An agent allowed to drive the guarded dWallet, with the HumanRail
+/// capability that delegates signing to it. Sub-limits are in the base
+/// units of whichever asset is being sent and apply per asset, on top of
+/// the shared asset limits; the daily one is accounted with the guard's
+/// `daily_window`, like the asset daily limits. 0 means no sub-limit.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct AgentEntry {
+    /// Agent Registry AgentProfile account
+    pub agent: Pubkey,
+    /// Delegation Capability from the principal to `agent`
+    pub capability: Pubkey,
+    pub per_tx_limit: u64,
+    pub daily_limit: u64,
+}
+
+impl AgentEntry {
+    pub const LEN: usize = 32 + 32 + 8 + 8;
+
+    pub fn validate(&self) -> Result<()> {
+        if self.per_tx_limit > 0 && self.daily_limit > 0 {
+            require!(
+                self.per_tx_limit <= self.daily_limit,
+                GuardError::InvalidLimitConfig
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Daily spend of one agent on one asset. The hourly buckets cover the
+/// last 24 hours, which also contain the current UTC day, so they serve
+/// either daily window.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct AgentSpend {
+    pub agent: Pubkey,
+    pub chain_id: u32,
+    pub asset_hash: [u8; 32],
+    pub daily: RollingDay,
+}
+
+impl AgentSpend {
+    pub const LEN: usize = 32 + 4 + 32 + RollingDay::LEN;
+
+    fn matches(&self, agent: &Pubkey, chain_id: u32, asset_hash: &[u8; 32]) -> bool {
+        &self.agent == agent && self.chain_id == chain_id && &self.asset_hash == asset_hash
+    }
+
+    fn spent_at(&self, now: i64, window: DailyWindow) -> u64 {
+        match window {
+            DailyWindow::CalendarDay => self.daily.spent_today(now),
+            DailyWindow::Rolling24h => self.daily.spent_at(now),
+        }
+    }
+}
+
+/// A single allowlisted recipient of a GuardedDwallet on one chain.
+/// `per_tx_cap` of 0 means the entry is bounded only by the policy limits.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct RecipientEntry {
+    pub chain_id: u32,
+    pub recipient_hash: [u8; 32],
+    pub label: [u8; 32],
+    pub per_tx_cap: u64,
+}
+
+impl RecipientEntry {
+    pub const LEN: usize = 4 + 32 + 32 + 8;
+}
+
+/// A policy change that loosens the guard. Applied immediately when the
+/// guard has no timelock, otherwise staged in a PendingPolicyChange.
+/// Tightening changes (removals, lower limits) never need to be staged.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum PolicyChange {
+    UpdatePolicy {
+        chain_id: u32,
+        asset_hash: [u8; 32],
+        limits: AssetLimits,
+        expires_at: i64,
+    },
+    AddChain {
+        chain_id: u32,
+    },
+    AddAsset {
+        chain_id: u32,
+        asset_hash: [u8; 32],
+        limits: AssetLimits,
+    },
+    AddRecipient {
+        entry: RecipientEntry,
+    },
+    SetPolicyChangeDelay {
+        delay_secs: i64,
+    },
+    SetVelocityLimits {
+        limits: VelocityLimits,
+    },
+    SetTradingWindow {
+        window: TradingWindow,
+    },
+    AddCosigner {
+        cosigner: Pubkey,
+    },
+    SetCosignRequired {
+        required: u8,
+    },
+    AddAgent {
+        entry: AgentEntry,
+    },
+    SetAgentLimits {
+        agent: Pubkey,
+        per_tx_limit: u64,
+        daily_limit: u64,
+    },
+    SetAllowedSchemes {
+        schemes: u8,
+    },
+    SetPrincipalDigests {
+        allowed: bool,
+    },
+    /// Lets release_dwallet hand the dWallet to `new_authority`.
+    ReleaseDwallet {
+        new_authority: Pubkey,
+    },
+}
+
+impl PolicyChange {
+    // 1 (variant tag) + largest variant (UpdatePolicy, 100 bytes), padded
+    pub const LEN: usize = 1 + 128;
+}
+
+/// PendingPolicyChange holds a proposed loosening change until the guard's
+/// `policy_change_delay` has elapsed. One per GuardedDwallet.
+#[account]
+pub struct PendingPolicyChange {
+    pub guarded_dwallet: Pubkey,
+    pub change: PolicyChange,
+    pub proposed_at: i64,
+    /// GuardedDwallet.policy_nonce at proposal time, for off-chain display.
+    pub policy_nonce: u64,
     pub bump: u8,
 }
 
-impl GuardedDwallet {
-    // 8 (discriminator) + 319 (fields) = 327; pad to 360 for safety
-    pub const LEN: usize = 360;
+impl PendingPolicyChange {
+    pub const LEN: usize = 32 + PolicyChange::LEN + 8 + 8 + 1;
+}
+
+/// Lifecycle of a GuardSigningRequest. Serialized as a single byte, so
+/// the variant order must not change.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SigningStatus {
+    /// Not decided yet. Never stored by approve_guarded_message.
+    Pending,
+    Approved,
+    Rejected,
+    /// Approved and confirmed signed by Ika via confirm_signing_request.
+    Signed,
+    /// Above the asset's co-sign threshold; waiting for cosign_guarded_message.
+    PendingCosign,
+}
+
+/// Why a signing request was rejected. Each variant other than `None`
+/// corresponds to a GuardError; see the conversions in error.rs.
+/// Serialized as a single byte, so new variants must be appended.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RejectionReason {
+    None,
+    Frozen,
+    Expired,
+    ChainNotAllowed,
+    AssetNotAllowed,
+    RecipientNotAllowed,
+    InvalidAmount,
+    PerTxLimitExceeded,
+    DailyLimitExceeded,
+    TotalLimitExceeded,
+    DwalletMismatch,
+    UnauthorizedPrincipal,
+    RecipientCapExceeded,
+    CapabilityInactive,
+    AgentNotActive,
+    HourlyLimitExceeded,
+    WeeklyLimitExceeded,
+    MonthlyLimitExceeded,
+    VelocityLimitExceeded,
+    OutsideTradingWindow,
+    NotYetActive,
+    CooldownActive,
+    CosignersNotConfigured,
+    AgentPerTxLimitExceeded,
+    AgentDailyLimitExceeded,
+    PayloadRequired,
+    InvalidPayload,
+    PayloadMismatch,
+    SignatureSchemeNotAllowed,
+    InvalidUserPubkey,
+    NotArmed,
+}
+
+impl RejectionReason {
+    /// The GuardError number for this reason, or 0 for `None`.
+    pub fn code(self) -> u16 {
+        match GuardError::try_from(self) {
+            Ok(err) => err as u16,
+            Err(_) => 0,
+        }
+    }
 }
 
 /// GuardSigningRequest records a single cross-chain signing attempt.
-/// Created by approve_guarded_message. Status indicates approved or rejected.
+/// Created by approve_guarded_message as approved, rejected or pending
+/// co-signature; approved requests move to signed once
+/// confirm_signing_request sees the signature.
 #[account]
 pub struct GuardSigningRequest {
     pub version: u8,
@@ -46,14 +1004,166 @@ pub struct GuardSigningRequest {
     pub recipient_hash: [u8; 32],
     pub amount: u64,
     pub signature_scheme: u16,
-    pub status: u8,
-    pub rejection_code: u16,
+    pub status: SigningStatus,
+    pub rejection_reason: RejectionReason,
     pub ika_message_approval: Pubkey,
     pub created_at: i64,
     pub bump: u8,
+    /// Account that paid rent for this request; refunded on close.
+    pub payer: Pubkey,
+    /// Ika approve_message inputs, kept so a PendingCosign request can be
+    /// sent to Ika once enough co-signers have approved it.
+    pub user_pubkey: [u8; 32],
+    pub message_approval_bump: u8,
+    pub cosigned_by: Vec<Pubkey>,
 }
 
 impl GuardSigningRequest {
-    // 8 (discriminator) + 379 (fields) = 387; pad to 440 for safety
-    pub const LEN: usize = 440;
+    pub const VERSION: u8 = 2;
+
+    // 8 (discriminator) + 671 (fields, cosigned_by at its maximum) = 679;
+    // pad to 720 for safety
+    pub const LEN: usize = 720;
+
+    /// How long an approved request is kept before it can be closed.
+    /// Rejected requests can be closed at any time.
+    pub const RETENTION_SECS: i64 = 7 * 86400;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3600;
+    /// 2024-03-09 23:00:00 UTC, a Saturday.
+    const SAT_23H: i64 = 1_710_025_200;
+    /// 2024-03-11 00:00:00 UTC, a Monday.
+    const MONDAY: i64 = 1_710_115_200;
+
+    #[test]
+    fn rolling_day_spans_midnight() {
+        let mut day = RollingDay::default();
+        day.record(100, SAT_23H);
+        day.record(50, SAT_23H + 2 * HOUR);
+        assert_eq!(day.spent_at(SAT_23H + 2 * HOUR), 150);
+        // The calendar day restarted at midnight
+        assert_eq!(day.spent_today(SAT_23H + 2 * HOUR), 50);
+        // 23:00 drops out of the window at 23:00 the next day
+        assert_eq!(day.spent_at(SAT_23H + 23 * HOUR), 150);
+        assert_eq!(day.spent_at(SAT_23H + 24 * HOUR), 50);
+    }
+
+    #[test]
+    fn rolling_day_clears_after_idle_day() {
+        let mut day = RollingDay::default();
+        day.record(100, SAT_23H);
+        assert!(!day.is_idle(SAT_23H + 23 * HOUR));
+        assert!(day.is_idle(SAT_23H + 24 * HOUR));
+        // Two days later the same bucket comes round again
+        day.record(30, SAT_23H + 48 * HOUR);
+        assert_eq!(day.spent_at(SAT_23H + 48 * HOUR), 30);
+        // After more than a day idle, nothing old is counted
+        day.record(20, SAT_23H + 90 * HOUR);
+        assert_eq!(day.spent_at(SAT_23H + 90 * HOUR), 20);
+        assert_eq!(day.spent_at(SAT_23H + 200 * HOUR), 0);
+    }
+
+    #[test]
+    fn rolling_day_clears_skipped_hours() {
+        let mut day = RollingDay::default();
+        for hour in 0..24 {
+            day.record(1, SAT_23H + hour * HOUR);
+        }
+        assert_eq!(day.spent_at(SAT_23H + 23 * HOUR), 24);
+        // Ten hours later, ten of the 24 buckets have expired
+        day.record(5, SAT_23H + 33 * HOUR);
+        assert_eq!(day.spent_at(SAT_23H + 33 * HOUR), 14 + 5);
+    }
+
+    #[test]
+    fn agent_spend_follows_daily_window() {
+        let mut spend = AgentSpend::default();
+        spend.daily.record(100, SAT_23H);
+        let after_midnight = SAT_23H + 2 * HOUR;
+        assert_eq!(spend.spent_at(after_midnight, DailyWindow::CalendarDay), 0);
+        assert_eq!(spend.spent_at(after_midnight, DailyWindow::Rolling24h), 100);
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        let week = SpendPeriod::Weekly.epoch(MONDAY);
+        assert_eq!(SpendPeriod::Weekly.epoch(MONDAY - 1), week - 1);
+        // Through Sunday 23:59:59
+        assert_eq!(SpendPeriod::Weekly.epoch(MONDAY + 7 * 86400 - 1), week);
+        assert_eq!(SpendPeriod::Weekly.epoch(MONDAY + 7 * 86400), week + 1);
+        // The week of 1970-01-01, a Thursday, is week 0
+        assert_eq!(SpendPeriod::Weekly.epoch(0), 0);
+        assert_eq!(SpendPeriod::Weekly.epoch(4 * 86400), 1);
+        assert_eq!(SpendPeriod::Weekly.epoch(-1), 0);
+        assert_eq!(SpendPeriod::Weekly.epoch(-3 * 86400 - 1), -1);
+    }
+
+    #[test]
+    fn months_follow_the_calendar() {
+        let month = |now| SpendPeriod::Monthly.epoch(now);
+        assert_eq!(month(0), 1970 * 12);
+        assert_eq!(month(-1), 1969 * 12 + 11);
+        // 2023-12-31 23:59:59, 2024-01-01
+        assert_eq!(month(1_704_067_199), 2023 * 12 + 11);
+        assert_eq!(month(1_704_067_200), 2024 * 12);
+        // 2024-02-01 and the leap day 2024-02-29 23:59:59, then 2024-03-01
+        assert_eq!(month(1_706_745_600), 2024 * 12 + 1);
+        assert_eq!(month(1_709_251_199), 2024 * 12 + 1);
+        assert_eq!(month(1_709_251_200), 2024 * 12 + 2);
+        // 2023-02-28 23:59:59, 2023-03-01
+        assert_eq!(month(1_677_628_799), 2023 * 12 + 1);
+        assert_eq!(month(1_677_628_800), 2023 * 12 + 2);
+        // 2000 is a leap year: 2000-02-29
+        assert_eq!(month(951_782_400), 2000 * 12 + 1);
+        // 2100 is not: 2100-02-28 23:59:59, then 2100-03-01
+        assert_eq!(month(4_107_542_399), 2100 * 12 + 1);
+        assert_eq!(month(4_107_542_400), 2100 * 12 + 2);
+    }
+
+    #[test]
+    fn hours_and_days_roll_over_at_utc_boundaries() {
+        assert_eq!(SpendPeriod::Hourly.epoch(MONDAY - 1) + 1, SpendPeriod::Hourly.epoch(MONDAY));
+        assert_eq!(SpendPeriod::Daily.epoch(MONDAY - 1) + 1, SpendPeriod::Daily.epoch(MONDAY));
+        assert_eq!(SpendPeriod::Daily.epoch(-1), -1);
+    }
+
+    fn window(hour: u32, weekday: u8, utc_offset_secs: i32) -> TradingWindow {
+        TradingWindow {
+            allowed_hours: 1 << hour,
+            allowed_weekdays: 1 << weekday,
+            utc_offset_secs,
+        }
+    }
+
+    #[test]
+    fn trading_window_uses_local_time() {
+        const UTC_MINUS_5: i32 = -5 * 3600;
+        // Monday 03:00 UTC is Sunday 22:00 at UTC-5
+        let now = MONDAY + 3 * HOUR;
+        assert!(window(22, 6, UTC_MINUS_5).contains(now));
+        assert!(!window(3, 0, UTC_MINUS_5).contains(now));
+        assert!(!window(22, 0, UTC_MINUS_5).contains(now));
+        assert!(window(3, 0, 0).contains(now));
+        // Sunday 20:00 UTC is Monday 05:00 at UTC+9
+        assert!(window(5, 0, 9 * 3600).contains(MONDAY - 4 * HOUR));
+        // 1970-01-01 02:00 UTC is Wednesday 21:00 at UTC-5
+        assert!(window(21, 2, UTC_MINUS_5).contains(2 * HOUR));
+        // Before the epoch: 1969-12-31 23:59:59 UTC, a Wednesday
+        assert!(window(23, 2, 0).contains(-1));
+    }
+
+    #[test]
+    fn trading_window_validation() {
+        assert!(TradingWindow::ALWAYS.validate().is_ok());
+        assert!(window(0, 0, -TradingWindow::MAX_UTC_OFFSET_SECS).validate().is_ok());
+        assert!(window(0, 0, TradingWindow::MAX_UTC_OFFSET_SECS + 1).validate().is_err());
+        assert!(window(0, 0, -TradingWindow::MAX_UTC_OFFSET_SECS - 1).validate().is_err());
+        assert!(window(0, 7, 0).validate().is_err());
+        assert!(window(24, 0, 0).validate().is_err());
+    }
 }
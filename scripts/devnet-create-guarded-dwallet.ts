@@ -3,14 +3,23 @@
  * Phase 4B / 5C — Create Guarded dWallet Policy on Devnet
  *
  * This script:
- * 1. Creates a GuardedDwallet policy using the devnet-only demo initializer
- *    (skips HumanRail owner checks since canRegisterAgents is blocked)
- * 2. Supports two modes:
- *    - Default (Phase 4B): uses placeholder dWallet
- *    - --real-ika (Phase 5C): uses real Ika dWallet PDA
- * 3. Tests freeze/unfreeze (Phase 4B only)
- * 4. Tests a rejected signing request (Phase 4B only)
- * 5. Fetches and verifies all accounts
+ * 1. Creates a GuardedDwallet policy for the Ika dWallet in
+ *    .local-ika/dwallet.json using the devnet-only demo initializer
+ *    (skips HumanRail owner checks since canRegisterAgents is blocked).
+ *    The dWallet authority must already be the Guard CPI PDA, so the guard
+ *    starts armed.
+ * 2. Turns on principal digests, so the principal can get the bare digest
+ *    sent by ika-approve-guarded-message.ts approved
+ * 3. Supports two modes:
+ *    - Default (Phase 4B): also exercises the guard
+ *    - --real-ika (Phase 5C): writes .local-ika/guarded-dwallet.json
+ * 4. Tests freeze/unfreeze (Phase 4B only)
+ * 5. Tests a rejected signing request (Phase 4B only)
+ * 6. Fetches and verifies all accounts
+ *
+ * initialize_guarded_dwallet_demo only exists in program builds with the
+ * `demo` feature, which must be the build deployed to devnet:
+ *   cd programs/humanrail-dwallet-guard && cargo build-sbf --features demo
  *
  * Usage:
  *   npx tsx scripts/devnet-create-guarded-dwallet.ts
@@ -24,6 +33,7 @@ import {
   Keypair,
   PublicKey,
   Transaction,
+  TransactionInstruction,
   SystemProgram,
 } from "@solana/web3.js";
 import { keccak_256 } from "@noble/hashes/sha3.js";
@@ -38,6 +48,14 @@ import {
   parseIkaDwalletAccount,
 } from "../lib/ika/parsers";
 import { deriveHumanRailGuardCpiAuthority } from "../lib/ika/pda";
+import {
+  DailyWindow,
+  buildApproveGuardedMessageIx,
+  buildFreezeGuardedDwalletIx,
+  buildInitializeGuardedDwalletDemoIx,
+  buildSetPrincipalDigestsIx,
+  buildUnfreezeGuardedDwalletIx,
+} from "../lib/dwallet-guard/instructions";
 
 // ---------------------------------------------------------------------------
 // Account discriminators from Anchor IDL
@@ -45,12 +63,6 @@ import { deriveHumanRailGuardCpiAuthority } from "../lib/ika/pda";
 const GUARDED_DWALLET_DISCRIMINATOR = Buffer.from([12, 125, 217, 8, 251, 154, 190, 73]);
 const GUARD_SIGNING_REQUEST_DISCRIMINATOR = Buffer.from([114, 10, 219, 237, 226, 180, 54, 33]);
 
-// Instruction discriminators
-const IX_INIT_DEMO = Buffer.from([119, 131, 122, 191, 76, 83, 31, 246]);
-const IX_FREEZE = Buffer.from([151, 57, 89, 252, 123, 234, 123, 61]);
-const IX_UNFREEZE = Buffer.from([223, 101, 174, 85, 26, 221, 221, 194]);
-const IX_APPROVE = Buffer.from([161, 49, 124, 159, 1, 54, 243, 30]);
-
 const RPC = "https://api.devnet.solana.com";
 const SLEEP_MS = 2000;
 
@@ -59,7 +71,6 @@ const PER_TX_LIMIT = BigInt(100_000_000);
 const DAILY_LIMIT = BigInt(500_000_000);
 const TOTAL_LIMIT = BigInt(1_000_000_000);
 
-const DEMO_DWALLET_LABEL = "DEMO_DWALLET_PLACEHOLDER_FOR_PHASE_4B";
 const REAL_IKA_LABEL = "REAL_IKA_DWALLET_WITH_DEMO_HUMANRAIL_REFS";
 
 const DWALLET_ARTIFACT_PATH = ".local-ika/dwallet.json";
@@ -81,10 +92,11 @@ function loadKeypair(): Keypair {
 
 async function sendTx(
   connection: Connection,
-  tx: Transaction,
+  ix: TransactionInstruction,
   signers: Keypair[]
 ): Promise<string> {
   const rb = await connection.getLatestBlockhash("confirmed");
+  const tx = new Transaction().add(ix);
   tx.recentBlockhash = rb.blockhash;
   tx.feePayer = signers[0].publicKey;
   tx.sign(...signers);
@@ -97,161 +109,9 @@ async function sendTx(
 }
 
 // ---------------------------------------------------------------------------
-// Instruction builders
-// ---------------------------------------------------------------------------
-function buildInitializeGuardedDwalletDemoIx(
-  guardProgramId: PublicKey,
-  params: {
-    principal: PublicKey;
-    guardedDwallet: PublicKey;
-    humanProfile: PublicKey;
-    agent: PublicKey;
-    humanrailCapability: PublicKey;
-    dwallet: PublicKey;
-    allowedChainId: number;
-    allowedAssetHash: Uint8Array;
-    allowedRecipientHash: Uint8Array;
-    perTxLimit: bigint;
-    dailyLimit: bigint;
-    totalLimit: bigint;
-    expiresAt: bigint;
-  }
-): Transaction {
-  const data = Buffer.alloc(8 + 4 + 32 + 32 + 8 + 8 + 8 + 8);
-  let offset = 0;
-  IX_INIT_DEMO.copy(data, offset);
-  offset += 8;
-  data.writeUInt32LE(params.allowedChainId, offset);
-  offset += 4;
-  Buffer.from(params.allowedAssetHash).copy(data, offset);
-  offset += 32;
-  Buffer.from(params.allowedRecipientHash).copy(data, offset);
-  offset += 32;
-  const view = new DataView(data.buffer, data.byteOffset + offset, 32);
-  view.setBigUint64(0, params.perTxLimit, true);
-  view.setBigUint64(8, params.dailyLimit, true);
-  view.setBigUint64(16, params.totalLimit, true);
-  view.setBigInt64(24, params.expiresAt, true);
-
-  const ix = {
-    keys: [
-      { pubkey: params.principal, isSigner: true, isWritable: true },
-      { pubkey: params.guardedDwallet, isSigner: false, isWritable: true },
-      { pubkey: params.humanProfile, isSigner: false, isWritable: false },
-      { pubkey: params.agent, isSigner: false, isWritable: false },
-      { pubkey: params.humanrailCapability, isSigner: false, isWritable: false },
-      { pubkey: params.dwallet, isSigner: false, isWritable: false },
-      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
-    ],
-    programId: guardProgramId,
-    data,
-  };
-  return new Transaction().add(ix as unknown as import("@solana/web3.js").TransactionInstruction);
-}
-
-function buildFreezeGuardedDwalletIx(
-  guardProgramId: PublicKey,
-  principal: PublicKey,
-  guardedDwallet: PublicKey
-): Transaction {
-  const ix = {
-    keys: [
-      { pubkey: principal, isSigner: true, isWritable: false },
-      { pubkey: guardedDwallet, isSigner: false, isWritable: true },
-    ],
-    programId: guardProgramId,
-    data: IX_FREEZE,
-  };
-  return new Transaction().add(ix as unknown as import("@solana/web3.js").TransactionInstruction);
-}
-
-function buildUnfreezeGuardedDwalletIx(
-  guardProgramId: PublicKey,
-  principal: PublicKey,
-  guardedDwallet: PublicKey
-): Transaction {
-  const ix = {
-    keys: [
-      { pubkey: principal, isSigner: true, isWritable: false },
-      { pubkey: guardedDwallet, isSigner: false, isWritable: true },
-    ],
-    programId: guardProgramId,
-    data: IX_UNFREEZE,
-  };
-  return new Transaction().add(ix as unknown as import("@solana/web3.js").TransactionInstruction);
-}
-
-function buildApproveGuardedMessageIx(
-  guardProgramId: PublicKey,
-  params: {
-    requester: PublicKey;
-    guardedDwallet: PublicKey;
-    guardSigningRequest: PublicKey;
-    dwallet: PublicKey;
-    cpiAuthority: PublicKey;
-    coordinator: PublicKey;
-    messageApproval: PublicKey;
-    requestId: Uint8Array;
-    messageDigest: Uint8Array;
-    messageMetadataDigest: Uint8Array;
-    destinationChainId: number;
-    assetHash: Uint8Array;
-    recipientHash: Uint8Array;
-    amount: bigint;
-    userPubkey: Uint8Array;
-    signatureScheme: number;
-    messageApprovalBump: number;
-  }
-): Transaction {
-  const data = Buffer.alloc(8 + 32 + 32 + 32 + 4 + 32 + 32 + 8 + 32 + 2 + 1);
-  let offset = 0;
-  IX_APPROVE.copy(data, offset);
-  offset += 8;
-  Buffer.from(params.requestId).copy(data, offset);
-  offset += 32;
-  Buffer.from(params.messageDigest).copy(data, offset);
-  offset += 32;
-  Buffer.from(params.messageMetadataDigest).copy(data, offset);
-  offset += 32;
-  data.writeUInt32LE(params.destinationChainId, offset);
-  offset += 4;
-  Buffer.from(params.assetHash).copy(data, offset);
-  offset += 32;
-  Buffer.from(params.recipientHash).copy(data, offset);
-  offset += 32;
-  const amountView = new DataView(data.buffer, data.byteOffset + offset, 8);
-  amountView.setBigUint64(0, params.amount, true);
-  offset += 8;
-  Buffer.from(params.userPubkey).copy(data, offset);
-  offset += 32;
-  data.writeUInt16LE(params.signatureScheme, offset);
-  offset += 2;
-  data.writeUInt8(params.messageApprovalBump, offset);
-
-  // Anchor expects all accounts in order, including Option<AccountInfo>.
-  // When agent_registry_account is None, pass the program ID as placeholder.
-  const keys = [
-    { pubkey: params.requester, isSigner: true, isWritable: true },
-    { pubkey: params.guardedDwallet, isSigner: false, isWritable: true },
-    { pubkey: params.guardSigningRequest, isSigner: false, isWritable: true },
-    { pubkey: params.dwallet, isSigner: false, isWritable: false },
-    { pubkey: guardProgramId, isSigner: false, isWritable: false }, // agent_registry_account: None placeholder
-    { pubkey: params.cpiAuthority, isSigner: false, isWritable: false },
-    { pubkey: guardProgramId, isSigner: false, isWritable: false }, // program (this program)
-    { pubkey: new PublicKey("87W54kGYFQ1rgWqMeu4XTPHWXWmXSQCcjm8vCTfiq1oY"), isSigner: false, isWritable: false },
-    { pubkey: params.coordinator, isSigner: false, isWritable: false },
-    { pubkey: params.messageApproval, isSigner: false, isWritable: true },
-    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
-  ];
-
-  return new Transaction().add({ keys, programId: guardProgramId, data } as unknown as import("@solana/web3.js").TransactionInstruction);
-}
-
-// ---------------------------------------------------------------------------
-// Account parsers
+// Account parsers (version 2 layouts, see programs/humanrail-dwallet-guard/src/state.rs)
 // ---------------------------------------------------------------------------
 function parseGuardedDwallet(data: Buffer) {
-  if (data.length < 8 + 319) throw new Error("GuardedDwallet data too short");
   const d = Buffer.from(data);
   const disc = d.slice(0, 8);
   if (!disc.equals(GUARDED_DWALLET_DISCRIMINATOR)) {
@@ -259,33 +119,39 @@ function parseGuardedDwallet(data: Buffer) {
   }
   let off = 8;
   const version = d.readUInt8(off); off += 1;
+  if (version !== 2) throw new Error(`Unsupported GuardedDwallet version ${version}`);
   const principal = new PublicKey(d.slice(off, off + 32)); off += 32;
   const humanProfile = new PublicKey(d.slice(off, off + 32)); off += 32;
-  const agent = new PublicKey(d.slice(off, off + 32)); off += 32;
-  const humanrailCapability = new PublicKey(d.slice(off, off + 32)); off += 32;
   const dwallet = new PublicKey(d.slice(off, off + 32)); off += 32;
-  const allowedChainId = d.readUInt32LE(off); off += 4;
-  const allowedAssetHash = d.slice(off, off + 32); off += 32;
-  const allowedRecipientHash = d.slice(off, off + 32); off += 32;
-  const perTxLimit = d.readBigUInt64LE(off); off += 8;
-  const dailyLimit = d.readBigUInt64LE(off); off += 8;
-  const totalLimit = d.readBigUInt64LE(off); off += 8;
-  const dailySpent = d.readBigUInt64LE(off); off += 8;
-  const totalSpent = d.readBigUInt64LE(off); off += 8;
-  const lastSpendDay = d.readBigInt64LE(off); off += 8;
   const expiresAt = d.readBigInt64LE(off); off += 8;
   const frozen = d.readUInt8(off) !== 0; off += 1;
   const bump = d.readUInt8(off); off += 1;
+  const policyNonce = d.readBigUInt64LE(off); off += 8;
+  off += 8; // policy_change_delay
+  off += 4 + 4; // outstanding_approvals, pending_cosigns
+  const demoMode = d.readUInt8(off) !== 0; off += 1;
+  // daily_window, velocity, hourly_approvals, consecutive_rejections,
+  // trading_window, not_before, min_interval_secs, last_approval_at,
+  // cosign_required, pending_principal, curve
+  off += 1 + 8 + 16 + 4 + 9 + 8 + 8 + 8 + 1 + 32;
+  const curve = d.readUInt16LE(off); off += 2;
+  const allowedSchemes = d.readUInt8(off); off += 1;
+  const principalDigests = d.readUInt8(off) !== 0; off += 1;
+  const armed = d.readUInt8(off) !== 0; off += 1;
+  off += 32 + 32; // pre_arm_authority, release_authority
+  const chainCount = d.readUInt32LE(off); off += 4;
+  const allowedChainIds: number[] = [];
+  for (let i = 0; i < chainCount; i++) {
+    allowedChainIds.push(d.readUInt32LE(off)); off += 4;
+  }
   return {
-    version, principal, humanProfile, agent, humanrailCapability, dwallet,
-    allowedChainId, allowedAssetHash, allowedRecipientHash,
-    perTxLimit, dailyLimit, totalLimit, dailySpent, totalSpent, lastSpendDay,
-    expiresAt, frozen, bump,
+    version, principal, humanProfile, dwallet, expiresAt, frozen, bump,
+    policyNonce, demoMode, curve, allowedSchemes, principalDigests, armed,
+    allowedChainIds,
   };
 }
 
 function parseGuardSigningRequest(data: Buffer) {
-  if (data.length < 8 + 379) throw new Error("GuardSigningRequest data too short");
   const d = Buffer.from(data);
   const disc = d.slice(0, 8);
   if (!disc.equals(GUARD_SIGNING_REQUEST_DISCRIMINATOR)) {
@@ -306,7 +172,7 @@ function parseGuardSigningRequest(data: Buffer) {
   const amount = d.readBigUInt64LE(off); off += 8;
   const signatureScheme = d.readUInt16LE(off); off += 2;
   const status = d.readUInt8(off); off += 1;
-  const rejectionCode = d.readUInt16LE(off); off += 2;
+  const rejectionReason = d.readUInt8(off); off += 1;
   const ikaMessageApproval = new PublicKey(d.slice(off, off + 32)); off += 32;
   const createdAt = d.readBigInt64LE(off); off += 8;
   const bump = d.readUInt8(off); off += 1;
@@ -314,7 +180,7 @@ function parseGuardSigningRequest(data: Buffer) {
     version, requestId, guardedDwallet, principal, agent, dwallet,
     messageDigest, messageMetadataDigest, destinationChainId,
     assetHash, recipientHash, amount, signatureScheme, status,
-    rejectionCode, ikaMessageApproval, createdAt, bump,
+    rejectionReason, ikaMessageApproval, createdAt, bump,
   };
 }
 
@@ -360,56 +226,45 @@ async function main() {
   // ------------------------------------------------------------------
   // 2. Determine dWallet
   // ------------------------------------------------------------------
-  let dwallet: PublicKey;
-  let dwalletLabel: string;
-
-  if (useRealIka) {
-    console.log("--- Mode: REAL IKA DWALLET ---");
-    if (!fs.existsSync(DWALLET_ARTIFACT_PATH)) {
-      console.error("ERROR: dWallet artifact not found:", DWALLET_ARTIFACT_PATH);
-      console.error("Run `npm run ika:create-dwallet` first.");
-      process.exit(1);
-    }
+  // The demo initializer reads the Ika dWallet account, so both modes need
+  // the real dWallet.
+  console.log(useRealIka ? "--- Mode: REAL IKA DWALLET ---" : "--- Mode: GUARD LIFECYCLE ---");
+  if (!fs.existsSync(DWALLET_ARTIFACT_PATH)) {
+    console.error("ERROR: dWallet artifact not found:", DWALLET_ARTIFACT_PATH);
+    console.error("Run `npm run ika:create-dwallet` first.");
+    process.exit(1);
+  }
 
-    const dwalletArtifact = JSON.parse(fs.readFileSync(DWALLET_ARTIFACT_PATH, "utf-8"));
-    dwallet = new PublicKey(dwalletArtifact.dwallet_pda);
-    dwalletLabel = REAL_IKA_LABEL;
+  const dwalletArtifact = JSON.parse(fs.readFileSync(DWALLET_ARTIFACT_PATH, "utf-8"));
+  const dwallet = new PublicKey(dwalletArtifact.dwallet_pda);
+  const dwalletLabel = REAL_IKA_LABEL;
 
-    console.log("dWallet PDA (from artifact):", dwallet.toBase58());
-    console.log("dWallet curve:", dwalletArtifact.curve);
+  console.log("dWallet PDA (from artifact):", dwallet.toBase58());
+  console.log("dWallet curve:", dwalletArtifact.curve);
 
-    // Verify dWallet authority == Guard CPI PDA
-    const dwalletInfo = await connection.getAccountInfo(dwallet);
-    if (!dwalletInfo) {
-      console.error("ERROR: dWallet account not found on-chain.");
-      process.exit(1);
-    }
+  // Verify dWallet authority == Guard CPI PDA, so the guard starts armed
+  const dwalletInfo = await connection.getAccountInfo(dwallet);
+  if (!dwalletInfo) {
+    console.error("ERROR: dWallet account not found on-chain.");
+    process.exit(1);
+  }
 
-    const parsed = parseIkaDwalletAccount(dwalletInfo.data as Buffer);
-    if (!parsed) {
-      console.error("ERROR: Failed to parse dWallet account.");
-      process.exit(1);
-    }
+  const parsedDwallet = parseIkaDwalletAccount(dwalletInfo.data as Buffer);
+  if (!parsedDwallet) {
+    console.error("ERROR: Failed to parse dWallet account.");
+    process.exit(1);
+  }
 
-    const [guardCpiAuthority] = deriveHumanRailGuardCpiAuthority(guardProgramId);
-    console.log("Expected authority (Guard CPI PDA):", guardCpiAuthority.toBase58());
-    console.log("Actual authority:", parsed.authority.toBase58());
+  const [guardCpiAuthority] = deriveHumanRailGuardCpiAuthority(guardProgramId);
+  console.log("Expected authority (Guard CPI PDA):", guardCpiAuthority.toBase58());
+  console.log("Actual authority:", parsedDwallet.authority.toBase58());
 
-    if (parsed.authority.toBase58() !== guardCpiAuthority.toBase58()) {
-      console.error("\nERROR: dWallet authority is NOT the Guard CPI PDA.");
-      console.error("Run `npm run ika:transfer-authority` first.");
-      process.exit(1);
-    }
-    console.log("  ✅ dWallet authority verified as Guard CPI PDA\n");
-  } else {
-    console.log("--- Mode: PLACEHOLDER DWALLET (Phase 4B) ---");
-    const dwalletSeed = "phase-4b-demo-dwallet-seed-v1";
-    dwallet = await PublicKey.createWithSeed(principal, dwalletSeed, SystemProgram.programId);
-    dwalletLabel = DEMO_DWALLET_LABEL;
-    console.log("dWallet pubkey (placeholder):", dwallet.toBase58());
-    console.log("  Label:", dwalletLabel);
-    console.log("  ✅ Placeholder ready\n");
+  if (parsedDwallet.authority.toBase58() !== guardCpiAuthority.toBase58()) {
+    console.error("\nERROR: dWallet authority is NOT the Guard CPI PDA.");
+    console.error("Run `npm run ika:transfer-authority` first.");
+    process.exit(1);
   }
+  console.log("  ✅ dWallet authority verified as Guard CPI PDA\n");
 
   // ------------------------------------------------------------------
   // 3. Demo reference accounts
@@ -427,12 +282,7 @@ async function main() {
   // 4. GuardedDwallet
   // ------------------------------------------------------------------
   console.log("--- Step 2: GuardedDwallet ---");
-  const [guardedDwalletPda, guardedBump] = deriveGuardedDwalletPda(
-    principal,
-    agent,
-    dwallet,
-    guardProgramId
-  );
+  const [guardedDwalletPda, guardedBump] = deriveGuardedDwalletPda(dwallet, guardProgramId);
   console.log("GuardedDwallet PDA:", guardedDwalletPda.toBase58());
   console.log("  Bump:", guardedBump);
 
@@ -451,13 +301,14 @@ async function main() {
     console.log("  total_limit:", TOTAL_LIMIT.toString());
     console.log("  expires_at:", new Date(Number(expiresAt) * 1000).toISOString());
 
-    const tx = buildInitializeGuardedDwalletDemoIx(guardProgramId, {
+    const ix = buildInitializeGuardedDwalletDemoIx(guardProgramId, {
       principal,
       guardedDwallet: guardedDwalletPda,
       humanProfile,
       agent,
       humanrailCapability,
       dwallet,
+      cpiAuthority: guardCpiAuthority,
       allowedChainId: DEMO_CHAIN_ID,
       allowedAssetHash: assetHash,
       allowedRecipientHash: recipientHash,
@@ -465,8 +316,11 @@ async function main() {
       dailyLimit: DAILY_LIMIT,
       totalLimit: TOTAL_LIMIT,
       expiresAt,
+      dailyWindow: DailyWindow.CalendarDay,
+      notBefore: BigInt(0),
+      minIntervalSecs: BigInt(0),
     });
-    const sig = await sendTx(connection, tx, [keypair]);
+    const sig = await sendTx(connection, ix, [keypair]);
     console.log("  initialize_guarded_dwallet_demo tx:", sig);
     await sleep(SLEEP_MS);
   }
@@ -477,23 +331,47 @@ async function main() {
     process.exit(1);
   }
 
-  const parsed = parseGuardedDwallet(guardedData.data);
+  let parsed = parseGuardedDwallet(guardedData.data);
+
+  // ika-approve-guarded-message.ts has the principal send a bare digest,
+  // which the guard only approves with principal digests on. A fresh guard
+  // has no policy change delay, so this applies immediately.
+  if (!parsed.principalDigests) {
+    const ix = buildSetPrincipalDigestsIx(guardProgramId, {
+      principal,
+      guardedDwallet: guardedDwalletPda,
+      allowed: true,
+    });
+    const sig = await sendTx(connection, ix, [keypair]);
+    console.log("  set_principal_digests tx:", sig);
+    await sleep(SLEEP_MS);
+
+    const updatedData = await connection.getAccountInfo(guardedDwalletPda);
+    if (!updatedData) throw new Error("GuardedDwallet disappeared after set_principal_digests");
+    parsed = parseGuardedDwallet(updatedData.data);
+  }
+
   console.log("\nParsed GuardedDwallet:");
   console.log("  version:", parsed.version);
   console.log("  principal:", parsed.principal.toBase58());
   console.log("  human_profile:", parsed.humanProfile.toBase58());
-  console.log("  agent:", parsed.agent.toBase58());
-  console.log("  humanrail_capability:", parsed.humanrailCapability.toBase58());
   console.log("  dwallet:", parsed.dwallet.toBase58());
-  console.log("  allowed_chain_id:", parsed.allowedChainId);
-  console.log("  per_tx_limit:", parsed.perTxLimit.toString());
-  console.log("  daily_limit:", parsed.dailyLimit.toString());
-  console.log("  total_limit:", parsed.totalLimit.toString());
-  console.log("  daily_spent:", parsed.dailySpent.toString());
-  console.log("  total_spent:", parsed.totalSpent.toString());
+  console.log("  allowed_chain_ids:", parsed.allowedChainIds.join(", "));
   console.log("  expires_at:", new Date(Number(parsed.expiresAt) * 1000).toISOString());
   console.log("  frozen:", parsed.frozen);
+  console.log("  armed:", parsed.armed);
+  console.log("  demo_mode:", parsed.demoMode);
+  console.log("  principal_digests:", parsed.principalDigests);
+  console.log("  policy_nonce:", parsed.policyNonce.toString());
   console.log("  bump:", parsed.bump);
+  if (!parsed.armed) {
+    console.error("ERROR: GuardedDwallet is not armed.");
+    process.exit(1);
+  }
+  if (!parsed.principalDigests) {
+    console.error("ERROR: Principal digests are off; set_principal_digests did not apply.");
+    process.exit(1);
+  }
   console.log("  ✅ GuardedDwallet created and parsed\n");
 
   // ------------------------------------------------------------------
@@ -505,8 +383,11 @@ async function main() {
     if (parsed.frozen) {
       console.log("Already frozen. Skipping freeze.");
     } else {
-      const tx = buildFreezeGuardedDwalletIx(guardProgramId, principal, guardedDwalletPda);
-      const sig = await sendTx(connection, tx, [keypair]);
+      const ix = buildFreezeGuardedDwalletIx(guardProgramId, {
+        authority: principal,
+        guardedDwallet: guardedDwalletPda,
+      });
+      const sig = await sendTx(connection, ix, [keypair]);
       console.log("  freeze_guarded_dwallet tx:", sig);
       await sleep(SLEEP_MS);
 
@@ -530,8 +411,11 @@ async function main() {
     if (!preUnfreezeParsed.frozen) {
       console.log("Already unfrozen. Skipping unfreeze.");
     } else {
-      const tx = buildUnfreezeGuardedDwalletIx(guardProgramId, principal, guardedDwalletPda);
-      const sig = await sendTx(connection, tx, [keypair]);
+      const ix = buildUnfreezeGuardedDwalletIx(guardProgramId, {
+        principal,
+        guardedDwallet: guardedDwalletPda,
+      });
+      const sig = await sendTx(connection, ix, [keypair]);
       console.log("  unfreeze_guarded_dwallet tx:", sig);
       await sleep(SLEEP_MS);
 
@@ -564,21 +448,21 @@ async function main() {
     console.log("GuardSigningRequest PDA:", guardSigningRequestPda.toBase58());
     console.log("  Bump:", requestBump);
 
-    const [cpiAuthority] = PublicKey.findProgramAddressSync(
-      [Buffer.from("__ika_cpi_authority")],
-      guardProgramId
-    );
-    console.log("CPI Authority PDA:", cpiAuthority.toBase58());
-
     const dummyCoordinator = Keypair.generate().publicKey;
     const dummyMessageApproval = Keypair.generate().publicKey;
 
-    const tx = buildApproveGuardedMessageIx(guardProgramId, {
+    // Over the per-tx limit, so it is rejected before any Ika CPI. The
+    // principal's bare digest passes the payload check now that principal
+    // digests are on, leaving the limit as the rejection reason.
+    const ix = buildApproveGuardedMessageIx(guardProgramId, {
       requester: principal,
       guardedDwallet: guardedDwalletPda,
       guardSigningRequest: guardSigningRequestPda,
       dwallet,
-      cpiAuthority,
+      agentRegistryAccount: null,
+      humanProfile,
+      humanrailCapability,
+      cpiAuthority: guardCpiAuthority,
       coordinator: dummyCoordinator,
       messageApproval: dummyMessageApproval,
       requestId,
@@ -591,8 +475,9 @@ async function main() {
       amount: rejectAmount,
       signatureScheme: 0,
       messageApprovalBump: 0,
+      payload: { kind: "digest" },
     });
-    const sig = await sendTx(connection, tx, [keypair]);
+    const sig = await sendTx(connection, ix, [keypair]);
     console.log("  approve_guarded_message tx:", sig);
     await sleep(SLEEP_MS);
 
@@ -614,7 +499,7 @@ async function main() {
     console.log("  amount:", parsedRequest.amount.toString());
     console.log("  signature_scheme:", parsedRequest.signatureScheme);
     console.log("  status:", parsedRequest.status, "(1=approved, 2=rejected)");
-    console.log("  rejection_code:", parsedRequest.rejectionCode, "(7=per_tx_limit_exceeded)");
+    console.log("  rejection_reason:", parsedRequest.rejectionReason, "(7=per_tx_limit_exceeded)");
     console.log("  ika_message_approval:", parsedRequest.ikaMessageApproval.toBase58());
     console.log("  created_at:", new Date(Number(parsedRequest.createdAt) * 1000).toISOString());
     console.log("  bump:", parsedRequest.bump);
@@ -623,11 +508,11 @@ async function main() {
       console.error("ERROR: Expected status=2 (rejected), got", parsedRequest.status);
       process.exit(1);
     }
-    if (parsedRequest.rejectionCode !== 7) {
-      console.error("ERROR: Expected rejection_code=7 (per_tx_limit_exceeded), got", parsedRequest.rejectionCode);
+    if (parsedRequest.rejectionReason !== 7) {
+      console.error("ERROR: Expected rejection_reason=7 (per_tx_limit_exceeded), got", parsedRequest.rejectionReason);
       process.exit(1);
     }
-    console.log("  ✅ Rejected request verified (status=2, code=7, no Ika CPI)\n");
+    console.log("  ✅ Rejected request verified (status=2, reason=7, no Ika CPI)\n");
   }
 
   // ------------------------------------------------------------------
@@ -658,6 +543,8 @@ async function main() {
       parsed: {
         version: parsed.version,
         frozen: parsed.frozen,
+        armed: parsed.armed,
+        principalDigests: parsed.principalDigests,
         bump: parsed.bump,
       },
       note: "Demo HumanRail refs; real Ika dWallet.",
@@ -691,8 +578,7 @@ async function main() {
   if (useRealIka) {
     console.log("\nNext step: approved approve_guarded_message → Phase 5D.");
   } else {
-    console.log("Placeholder dWallet used:", DEMO_DWALLET_LABEL);
-    console.log("Real Ika dWallet + authority transfer → Phase 5.");
+    console.log("\nRun with --real-ika to write the guarded-dwallet artifact for Phase 5D.");
   }
 }
 
@@ -4,7 +4,8 @@
  *
  * This script:
  * 1. Reads dWallet and GuardedDwallet artifacts
- * 2. Verifies preconditions (dWallet exists, authority == Guard CPI, policy exists, not frozen)
+ * 2. Verifies preconditions (dWallet exists, authority == Guard CPI, policy exists, not frozen,
+ *    principal digests turned on)
  * 3. Builds a policy-valid signing request
  * 4. Derives GuardSigningRequest PDA and Ika MessageApproval PDA
  * 5. Sends approve_guarded_message instruction (Guard program CPI-calls Ika approve_message)
@@ -187,10 +188,21 @@ async function main() {
   }
   console.log("GuardedDwallet account size:", guardedInfo.data.length, "bytes");
 
-  // Parse GuardedDwallet manually (skip 8-byte discriminator)
+  // Parse GuardedDwallet manually (skip 8-byte discriminator). Version 2
+  // layout: version, principal, human_profile, dwallet, expires_at, frozen, ...
   const gdData = guardedInfo.data as Buffer;
-  const gdDwallet = new PublicKey(gdData.slice(8 + 1 + 32 + 32 + 32 + 32, 8 + 1 + 32 + 32 + 32 + 32 + 32));
-  const gdFrozen = gdData[8 + 1 + 32 + 32 + 32 + 32 + 32 + 4 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1] === 1;
+  const gdDwallet = new PublicKey(gdData.slice(8 + 1 + 32 + 32, 8 + 1 + 32 + 32 + 32));
+  const gdFrozen = gdData[8 + 1 + 32 + 32 + 32 + 8] === 1;
+  // bump, policy_nonce, policy_change_delay, outstanding_approvals,
+  // pending_cosigns, demo_mode, daily_window, velocity, hourly_approvals,
+  // consecutive_rejections, trading_window, not_before, min_interval_secs,
+  // last_approval_at, cosign_required, pending_principal, curve,
+  // allowed_schemes, then principal_digests
+  const gdPrincipalDigests =
+    gdData[
+      8 + 1 + 32 + 32 + 32 + 8 + 1 +
+        1 + 8 + 8 + 4 + 4 + 1 + 1 + 8 + 16 + 4 + 9 + 8 + 8 + 8 + 1 + 32 + 2 + 1
+    ] === 1;
 
   if (gdDwallet.toBase58() !== dwalletPda.toBase58()) {
     console.error("\nERROR: GuardedDwallet.dwallet does not match real dWallet PDA.");
@@ -202,7 +214,13 @@ async function main() {
     console.error("\nERROR: GuardedDwallet is frozen.");
     process.exit(1);
   }
-  console.log("✅ GuardedDwallet linked to real dWallet and not frozen");
+  if (!gdPrincipalDigests) {
+    console.error("\nERROR: GuardedDwallet does not accept bare digests from the principal.");
+    console.error("  Turn them on with set_principal_digests (buildSetPrincipalDigestsIx); with a");
+    console.error("  policy change delay set, propose and execute SetPrincipalDigests instead.");
+    process.exit(1);
+  }
+  console.log("✅ GuardedDwallet linked to real dWallet, not frozen, principal digests on");
 
   // 5. Build policy-valid request
   const preimage = "HumanRail Mandara demo approved request: Base Sepolia USDC transfer 42";
@@ -328,6 +346,8 @@ async function main() {
     guardSigningRequest: guardSigningRequestPda,
     dwallet: dwalletPda,
     agentRegistryAccount: null, // principal signs directly, no agent registry needed
+    humanProfile: new PublicKey(guardedArtifact.humanProfile),
+    humanrailCapability: new PublicKey(guardedArtifact.humanrailCapability),
     cpiAuthority: guardCpiAuthority,
     coordinator: coordinatorPda,
     messageApproval: messageApprovalPda,
@@ -341,6 +361,9 @@ async function main() {
     userPubkey: payer.publicKey.toBytes(),
     signatureScheme,
     messageApprovalBump: maBump,
+    // The principal signs, so a bare digest is approved on a guard with
+    // principal digests on (checked above); agents must send a payload.
+    payload: { kind: "digest" },
   });
 
   console.log("\nInstruction accounts:");